@@ -135,27 +135,37 @@ async fn return_error(r: Rejection) -> Result<impl Reply, Rejection> {
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::RANGE_NOT_SATISFIABLE,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<CorsForbidden>() {
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::FORBIDDEN,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<BodyDeserializeError>() {
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )
+        .into_response())
     } else if let Some(InvalidId) = r.find() {
         Ok(warp::reply::with_status(
             "No valid ID presented".to_string(),
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )
+        .into_response())
     } else {
-        Ok(warp::reply::with_status(
-            "Route not found".to_string(),
-            StatusCode::NOT_FOUND,
-        ))
+        // 다른 JSON 응답과 형식을 맞추기 위해 본문을 JSON으로 보낸다.
+        Ok(warp::reply::with_header(
+            warp::reply::with_status(
+                r#"{"message":"Route not found","status":404}"#.to_string(),
+                StatusCode::NOT_FOUND,
+            ),
+            "content-type",
+            "application/json",
+        )
+        .into_response())
     }
 }
 
@@ -34,23 +34,32 @@ pub async fn return_error(r: Rejection) -> Result<impl Reply, Rejection> {
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::RANGE_NOT_SATISFIABLE,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<CorsForbidden>() {
         println!("{:?}", error);
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::FORBIDDEN,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<BodyDeserializeError>() {
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )
+        .into_response())
     } else {
         println!("{:?}", r);
-        Ok(warp::reply::with_status(
-            "Route not found".to_string(),
-            StatusCode::NOT_FOUND,
-        ))
+        // 다른 JSON 응답과 형식을 맞추기 위해 본문을 JSON으로 보낸다.
+        Ok(warp::reply::with_header(
+            warp::reply::with_status(
+                r#"{"message":"Route not found","status":404}"#.to_string(),
+                StatusCode::NOT_FOUND,
+            ),
+            "content-type",
+            "application/json",
+        )
+        .into_response())
     }
 }
@@ -72,55 +72,69 @@ pub async fn return_error(r: Rejection) -> Result<impl Reply, Rejection> {
         Ok(warp::reply::with_status(
             crate::Error::DatabaseQueryError.to_string(),
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )
+        .into_response())
     } else if let Some(crate::Error::ReqwestAPIError(e)) = r.find() {
         // 새로운 에러를 확인하고, 에러를 발견하면 세부 정보를 기록하고 클라이언트에게 500을 반환하는 if/else 블록을 확장한다.
         event!(Level::ERROR, "{}", e);
         Ok(warp::reply::with_status(
             "Internal Server Error".to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        )
+        .into_response())
     } else if let Some(crate::Error::MiddlewareReqwestAPIError(e)) = r.find() {
         event!(Level::ERROR, "{}", e);
         Ok(warp::reply::with_status(
             "Internal Server Error".to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        )
+        .into_response())
     } else if let Some(crate::Error::ClientError(e)) = r.find() {
         event!(Level::ERROR, "{}", e);
         Ok(warp::reply::with_status(
             "Internal Server Error".to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        )
+        .into_response())
     } else if let Some(crate::Error::ServerError(e)) = r.find() {
         event!(Level::ERROR, "{}", e);
         Ok(warp::reply::with_status(
             "Internal Server Error".to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<CorsForbidden>() {
         event!(Level::ERROR, "CORS forbidden error: {}", error);
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::FORBIDDEN,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<BodyDeserializeError>() {
         event!(Level::ERROR, "Cannot deserialize request body: {}", error);
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<Error>() {
         event!(Level::ERROR, "{}", error);
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )
+        .into_response())
     } else {
         event!(Level::WARN, "Requested route was not found");
-        Ok(warp::reply::with_status(
-            "Route not found".to_string(),
-            StatusCode::NOT_FOUND,
-        ))
+        // 다른 JSON 응답과 형식을 맞추기 위해 본문을 JSON으로 보낸다.
+        Ok(warp::reply::with_header(
+            warp::reply::with_status(
+                r#"{"message":"Route not found","status":404}"#.to_string(),
+                StatusCode::NOT_FOUND,
+            ),
+            "content-type",
+            "application/json",
+        )
+        .into_response())
     }
 }
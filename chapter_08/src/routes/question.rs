@@ -50,7 +50,10 @@ pub async fn get_questions(
     {
         Ok(res) => res,
         Err(e) => {
-            return Err(warp::reject::custom(Error::DatabaseQueryError(e))); // 에러의 경우, handle-errors 크레이트에서 정의한 에러 값을 에러 핸들러에 넘긴다.
+            event!(Level::ERROR, "{:?}", e);
+            return Err(warp::reject::custom(
+                handle_errors::Error::DatabaseQueryError,
+            )); // 에러의 경우, handle-errors 크레이트에서 정의한 에러 값을 에러 핸들러에 넘긴다.
         }
     };
 
@@ -63,14 +66,14 @@ pub async fn add_question(
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let title = match check_profanity(new_question.title).await { // 함수를 호출하고 퓨처를 기다린 후 Result에 일치시킨다.
         Ok(res) => res,
-        Err(e) => return Err(warp::reject::custom(e)).
+        Err(e) => return Err(warp::reject::custom(e)),
     };
 
     let content = match check_profanity(new_question.content).await { // 이 작업을 두 번째로 한다. 첫 번째는 title이었다. 이제 질문 자체 안에 있는 금칙어를 검사한다.
         Ok(res) => res,
-        Err(e) => return Err(warp::reject::custom(e)).
+        Err(e) => return Err(warp::reject::custom(e)),
     };
-    
+
     let question = NewQuestion {
         title: title,
         content,
@@ -79,7 +82,9 @@ pub async fn add_question(
 
     match store.add_question(question).await {
         Ok(question) => Ok(warp::reply::json(&question)), // 여기까지 왔다면 단순한 문자열과 HTTP 코드 대신에 정확한 질문을 반환한다.
-        Err(e) => Err(warp::reject::custom(e)),
+        Err(_) => Err(warp::reject::custom(
+            handle_errors::Error::DatabaseQueryError,
+        )),
     }
 }
 
@@ -163,16 +168,24 @@ pub async fn update_question(
     };
     match store.update_question(question, id).await {
         Ok(res) => Ok(warp::reply::json(&res)),
-        Err(e) => Err(warp::reject::custom(e)),
+        Err(_) => Err(warp::reject::custom(
+            handle_errors::Error::DatabaseQueryError,
+        )),
     }
 }
 
-pub async fn delete_question(
-    id: String,
-    store: Store,
-) -> Result<impl warp::Reply, warp::Rejection> {
-    match store.questions.write().await.remove(&QuestionId(id)) {
-        Some(_) => return Ok(warp::reply::with_status("Question deleted", StatusCode::OK)),
-        None => return Err(warp::reject::custom(Error::QuestionNotFound)),
+pub async fn delete_question(id: i32, store: Store) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.delete_question(id).await {
+        Ok(true) => Ok(warp::reply::with_status(
+            format!("Question {} deleted", id),
+            StatusCode::OK,
+        )),
+        Ok(false) => Ok(warp::reply::with_status(
+            format!("Question {} not found", id),
+            StatusCode::NOT_FOUND,
+        )),
+        Err(_) => Err(warp::reject::custom(
+            handle_errors::Error::DatabaseQueryError,
+        )),
     }
 }
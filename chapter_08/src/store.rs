@@ -6,7 +6,7 @@ use handle_errors::Error;
 
 use crate::types::{
     answer::{Answer, AnswerId},
-    question::{Question, QuestionId},
+    question::{NewQuestion, Question, QuestionId},
 };
 
 #[derive(Debug, Clone)]
@@ -108,4 +108,19 @@ impl Store {
             Err(e) => Err(e),
         }
     }
+
+    pub async fn delete_question(&self, question_id: i32) -> Result<bool, sqlx::Error> {
+        match sqlx::query("DELETE FROM questions WHERE id = $1")
+            .bind(question_id)
+            .execute(&self.connection)
+            .await
+        {
+            // 지운 행이 없으면 해당 질문이 없는 것이므로 false를 돌려준다.
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(e) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", e);
+                Err(e)
+            }
+        }
+    }
 }
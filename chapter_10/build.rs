@@ -1,5 +1,9 @@
 use platforms::*;
-use std::{borrow::Cow, process::Command};
+use std::{
+    borrow::Cow,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 /// `cargo:` 키 출력을 생성
 pub fn generate_cargo_keys() {
@@ -26,6 +30,25 @@ pub fn generate_cargo_keys() {
         "cargo:rustc-env=RUST_WEB_DEV_VERSION={}",
         get_version(&commit)
     );
+    // GET /version에서 빌드 정보를 보여주기 위해 커밋 해시와 빌드 시각(유닉스 초)을 따로 내보낸다.
+    println!("cargo:rustc-env=RUST_WEB_DEV_GIT_SHA={}", commit);
+    println!(
+        "cargo:rustc-env=RUST_WEB_DEV_BUILD_TIMESTAMP={}",
+        get_build_timestamp()
+    );
+}
+
+fn get_build_timestamp() -> u64 {
+    // 재현 가능한 빌드를 위해 SOURCE_DATE_EPOCH가 있으면 그 값을 쓴다.
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|val| val.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default()
+        })
 }
 
 fn get_platform() -> String {
@@ -3,7 +3,7 @@ use warp::{
     Rejection, Reply,
     filters::{body::BodyDeserializeError, cors::CorsForbidden},
     http::StatusCode,
-    reject::Reject,
+    reject::{LengthRequired, PayloadTooLarge, Reject},
 };
 
 use reqwest::Error as ReqwestError;
@@ -15,16 +15,42 @@ use tracing::{Level, event, instrument};
 pub enum Error {
     ParseError(std::num::ParseIntError),
     MissingParameters,
+    MissingParameter(String), // 필요한 쿼리 매개변수 중 빠진 것의 이름
     WrongPassword,
     CannotDecryptToken,
     Unauthorized,
     ArgonLibraryError(ArgonError),
     DatabaseQueryError(sqlx::Error), // DatabaseQueryError에 점검해야 할 sqlx::Error를 매개변수로 추가한다.
     MigrationError(sqlx::migrate::MigrateError),
-    ReqwestAPIError(ReqwestError),
-    MiddlewareReqwestAPIError(MiddlewareReqwestError),
+    ReqwestAPIError(ReqwestError), // 요청 전송, 시간 초과, 응답 디코딩 등 reqwest 자체에서 발생한 에러
+    MiddlewareReqwestAPIError(MiddlewareReqwestError), // 재시도 등 미들웨어에서 발생한 에러로, reqwest 에러는 포함하지 않는다.
     ClientError(APILayerError), // HTTP 클라이언트(Reqwest) 에서 에러가 발생할 경우를 위해 ClientError 열거 값을 만든다.
     ServerError(APILayerError), // 외부 API에서 4xx이나 5xx HTTP 상태 코드를 반환하는 경우를 위해 ServerError 열거 값을 만든다.
+    MissingEnvVar(String), // 필요한 환경 변수가 설정되지 않은 경우로, 외부 API가 아닌 서버 자체의 문제이다.
+    TooManyAnswers(i64), // 한 계정이 질문 하나에 남길 수 있는 답변 수를 넘긴 경우
+    TooManyRequests,
+    InvalidInput(String), // 요청 값이 형식은 맞지만 유효하지 않은 경우(빈 내용 등)
+    UnknownParameters(Vec<String>), // 엄격 모드에서 알 수 없는 쿼리 매개변수가 들어온 경우
+    UnknownFields(Vec<String>), // fields 매개변수에 반환할 수 없는 필드 이름이 들어온 경우
+    SuspiciousQuery(Vec<String>), // 쿼리 매개변수 값에 SQL 메타 문자가 들어 있는 경우
+    QuestionLocked, // 잠긴 질문에 답변을 달려는 경우
+    UnsupportedMediaType,
+    QuestionNotFound,
+    AnswerNotFound,
+    AccountNotFound, // 토큰에 담긴 계정이 그 사이에 삭제된 경우
+    ApiKeyNotFound, // 폐기하려는 API 키가 없거나 다른 계정의 키인 경우
+    EmailTaken, // 바꾸려는 이메일을 다른 계정이 이미 쓰고 있는 경우
+    EmailNotVerified, // REQUIRE_VERIFIED_EMAIL=true일 때 이메일을 인증하지 않은 계정이 쓰기 요청을 보낸 경우
+    TooMuchProfanity(i64), // 금칙어 수가 허용된 최대값을 넘은 경우
+    RangeNotSatisfiable, // Range 헤더의 형식이 잘못되었거나 범위에 아이템이 없는 경우
+    InvalidStatusTransition(String, String), // 허용되지 않는 질문 상태 변경(현재 상태, 바꾸려는 상태)
+    ServiceUnavailable, // 동시에 처리 중인 요청 수가 한도를 넘은 경우
+    BannedWord, // 로컬 금칙어 목록에 있는 단어가 들어 있는 경우
+    NotAcceptable, // Accept 헤더가 요구하는 형식으로 응답할 수 없는 경우
+    HttpsRequired(Option<String>), // HTTP로 들어온 요청으로, 값이 있으면 그 HTTPS 주소로 리디렉션한다.
+    AnswerConflict, // 답변이 유일성 제약 조건을 어긴 경우
+    DuplicateQuestion(i32), // 제목이 매우 비슷한 질문이 이미 있는 경우(기존 질문의 id)
+    TaskFailed(String), // 백그라운드 작업이 패닉하거나 취소되어 결과를 받지 못한 경우
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +76,9 @@ impl std::fmt::Display for Error {
             Error::MissingParameters => {
                 write!(f, "Missing parameters")
             }
+            Error::MissingParameter(name) => {
+                write!(f, "Missing parameter: {}", name)
+            }
             Error::WrongPassword => {
                 write!(f, "Wrong password")
             }
@@ -80,6 +109,81 @@ impl std::fmt::Display for Error {
             Error::ServerError(err) => {
                 write!(f, "External Server error: {}", err)
             }
+            Error::MissingEnvVar(name) => {
+                write!(f, "Environment variable not set: {}", name)
+            }
+            Error::TaskFailed(reason) => {
+                write!(f, "Background task failed: {}", reason)
+            }
+            Error::TooManyAnswers(limit) => {
+                write!(f, "Cannot add more than {} answers to a question", limit)
+            }
+            Error::TooManyRequests => {
+                write!(f, "Too many requests")
+            }
+            Error::InvalidInput(message) => {
+                write!(f, "Invalid input: {}", message)
+            }
+            Error::UnknownParameters(params) => {
+                write!(f, "Unknown query parameters: {}", params.join(", "))
+            }
+            Error::UnknownFields(fields) => {
+                write!(f, "Unknown fields: {}", fields.join(", "))
+            }
+            Error::SuspiciousQuery(params) => {
+                write!(f, "Suspicious query parameters: {}", params.join(", "))
+            }
+            Error::QuestionLocked => {
+                write!(f, "Question is locked")
+            }
+            Error::UnsupportedMediaType => {
+                write!(f, "Content-Type must be application/json")
+            }
+            Error::QuestionNotFound => {
+                write!(f, "Question not found")
+            }
+            Error::AnswerNotFound => {
+                write!(f, "Answer not found")
+            }
+            Error::AccountNotFound => {
+                write!(f, "Account not found")
+            }
+            Error::DuplicateQuestion(id) => {
+                write!(f, "Similar question already exists: {}", id)
+            }
+            Error::HttpsRequired(_) => {
+                write!(f, "HTTPS required")
+            }
+            Error::ApiKeyNotFound => {
+                write!(f, "API key not found")
+            }
+            Error::EmailTaken => {
+                write!(f, "Email is already in use")
+            }
+            Error::EmailNotVerified => {
+                write!(f, "Email address is not verified")
+            }
+            Error::TooMuchProfanity(max) => {
+                write!(f, "Content contains more than {} bad words", max)
+            }
+            Error::RangeNotSatisfiable => {
+                write!(f, "Requested range not satisfiable")
+            }
+            Error::InvalidStatusTransition(from, to) => {
+                write!(f, "Cannot change question status from {} to {}", from, to)
+            }
+            Error::ServiceUnavailable => {
+                write!(f, "Service unavailable")
+            }
+            Error::BannedWord => {
+                write!(f, "Content contains a banned word")
+            }
+            Error::NotAcceptable => {
+                write!(f, "Accept header must allow application/json")
+            }
+            Error::AnswerConflict => {
+                write!(f, "Answer already exists")
+            }
         }
     }
 }
@@ -87,7 +191,44 @@ impl std::fmt::Display for Error {
 impl Reject for Error {}
 impl Reject for APILayerError {}
 
+impl From<ReqwestError> for Error {
+    fn from(error: ReqwestError) -> Self {
+        Error::ReqwestAPIError(error)
+    }
+}
+
+impl From<MiddlewareReqwestError> for Error {
+    // 미들웨어가 감싼 reqwest 에러는 꺼내서 ReqwestAPIError로 통일한다.
+    // 어디서 발생했든 시간 초과는 같은 상태 코드로 처리된다.
+    fn from(error: MiddlewareReqwestError) -> Self {
+        match error {
+            MiddlewareReqwestError::Reqwest(error) => Error::ReqwestAPIError(error),
+            error => Error::MiddlewareReqwestAPIError(error),
+        }
+    }
+}
+
 const DUPLICATE_KEY: u32 = 23505;
+const QUERY_CANCELED: u32 = 57014; // statement_timeout을 넘겨 쿼리가 취소된 경우
+const POOL_TIMED_OUT_RETRY_AFTER_SECONDS: u64 = 1; // 연결 풀이 가득 찼을 때 클라이언트에게 알려줄 재시도 대기 시간
+
+// {"message": ..., "status": ...} 형태의 JSON 에러 응답을 만든다.
+// message는 고정된 문자열만 넘기므로 따로 이스케이프하지 않는다.
+fn json_error(message: &str, status: StatusCode) -> warp::reply::Response {
+    warp::reply::with_header(
+        warp::reply::with_status(
+            format!(
+                r#"{{"message":"{}","status":{}}}"#,
+                message,
+                status.as_u16()
+            ),
+            status,
+        ),
+        "content-type",
+        "application/json",
+    )
+    .into_response()
+}
 
 #[instrument]
 pub async fn return_error(r: Rejection) -> Result<impl Reply, Rejection> {
@@ -103,79 +244,283 @@ pub async fn return_error(r: Rejection) -> Result<impl Reply, Rejection> {
                     Ok(warp::reply::with_status(
                         "Account already exists".to_string(), // 우리가 찾는 코드가 맞다면, 계정이 이미 존재한다는 메시지를 반환한다.
                         StatusCode::UNPROCESSABLE_ENTITY,
-                    ))
+                    )
+                    .into_response())
+                } else if err.code().unwrap().parse::<u32>().unwrap() == QUERY_CANCELED {
+                    Ok(warp::reply::with_status(
+                        "Gateway Timeout".to_string(),
+                        StatusCode::GATEWAY_TIMEOUT,
+                    )
+                    .into_response())
                 } else {
                     Ok(warp::reply::with_status(
                         "Cannot update data".to_string(),
                         StatusCode::UNPROCESSABLE_ENTITY,
-                    ))
+                    )
+                    .into_response())
                 }
             }
+            // 연결 풀이 모두 사용 중이라 연결을 얻지 못한 경우로, 잠시 뒤 다시 시도하면 되는 일시적인 에러이다.
+            sqlx::Error::PoolTimedOut => Ok(warp::reply::with_header(
+                warp::reply::with_status(
+                    "Service unavailable".to_string(),
+                    StatusCode::SERVICE_UNAVAILABLE,
+                ),
+                "Retry-After",
+                POOL_TIMED_OUT_RETRY_AFTER_SECONDS.to_string(),
+            )
+            .into_response()),
             _ => Ok(warp::reply::with_status(
                 "Cannot update data".to_string(),
                 StatusCode::UNPROCESSABLE_ENTITY,
-            )),
+            )
+            .into_response()),
         }
     } else if let Some(crate::Error::ReqwestAPIError(e)) = r.find() {
         // 새로운 에러를 확인하고, 에러를 발견하면 세부 정보를 기록하고 클라이언트에게 500을 반환하는 if/else 블록을 확장한다.
         event!(Level::ERROR, "{}", e);
+        if e.is_timeout() {
+            // 외부 API가 제시간에 응답하지 않았다면 클라이언트가 재시도할 수 있도록 504를 반환한다.
+            return Ok(warp::reply::with_status(
+                "Gateway Timeout".to_string(),
+                StatusCode::GATEWAY_TIMEOUT,
+            )
+            .into_response());
+        }
         Ok(warp::reply::with_status(
             "Internal Server Error".to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        )
+        .into_response())
     } else if let Some(crate::Error::Unauthorized) = r.find() {
         event!(Level::ERROR, "Not matching account id");
         Ok(warp::reply::with_status(
             "No permission to change underlying resource".to_string(),
             StatusCode::UNAUTHORIZED,
-        ))
+        )
+        .into_response())
+    } else if let Some(crate::Error::TooManyRequests) = r.find() {
+        event!(Level::WARN, "Rate limit exceeded");
+        Ok(warp::reply::with_status(
+            "Too many requests".to_string(),
+            StatusCode::TOO_MANY_REQUESTS,
+        )
+        .into_response())
+    } else if let Some(crate::Error::NotAcceptable) = r.find() {
+        event!(Level::WARN, "Request with unsupported Accept header");
+        Ok(warp::reply::with_status(
+            "Accept header must allow application/json".to_string(),
+            StatusCode::NOT_ACCEPTABLE,
+        )
+        .into_response())
+    } else if let Some(crate::Error::HttpsRequired(location)) = r.find() {
+        event!(Level::WARN, "Plain HTTP request rejected");
+        match location {
+            // 308은 메서드와 본문을 그대로 유지하므로 POST, PUT 요청도 HTTPS로 다시 보내진다.
+            Some(location) => Ok(warp::reply::with_header(
+                warp::reply::with_status(warp::reply(), StatusCode::PERMANENT_REDIRECT),
+                "Location",
+                location.clone(),
+            )
+            .into_response()),
+            None => Ok(json_error("HTTPS required", StatusCode::FORBIDDEN)),
+        }
+    } else if let Some(crate::Error::ServiceUnavailable) = r.find() {
+        event!(Level::WARN, "Concurrency limit exceeded");
+        Ok(warp::reply::with_status(
+            "Service unavailable".to_string(),
+            StatusCode::SERVICE_UNAVAILABLE,
+        )
+        .into_response())
+    } else if let Some(crate::Error::UnknownFields(fields)) = r.find() {
+        event!(Level::WARN, "Unknown fields: {:?}", fields);
+        Ok(warp::reply::with_status(
+            format!("Unknown fields: {}", fields.join(", ")),
+            StatusCode::BAD_REQUEST,
+        )
+        .into_response())
+    } else if let Some(crate::Error::SuspiciousQuery(params)) = r.find() {
+        event!(Level::WARN, "Rejected suspicious query parameters: {:?}", params);
+        Ok(warp::reply::with_status(
+            format!("Suspicious query parameters: {}", params.join(", ")),
+            StatusCode::BAD_REQUEST,
+        )
+        .into_response())
+    } else if let Some(crate::Error::UnknownParameters(params)) = r.find() {
+        event!(Level::WARN, "Unknown query parameters: {:?}", params);
+        Ok(warp::reply::with_status(
+            format!("Unknown query parameters: {}", params.join(", ")),
+            StatusCode::BAD_REQUEST,
+        )
+        .into_response())
+    } else if let Some(crate::Error::QuestionLocked) = r.find() {
+        event!(Level::WARN, "Tried to answer a locked question");
+        Ok(warp::reply::with_status(
+            "Question is locked".to_string(),
+            StatusCode::FORBIDDEN,
+        )
+        .into_response())
+    } else if let Some(crate::Error::UnsupportedMediaType) = r.find() {
+        event!(Level::WARN, "Request without JSON Content-Type");
+        Ok(warp::reply::with_status(
+            "Content-Type must be application/json".to_string(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        )
+        .into_response())
+    } else if let Some(crate::Error::QuestionNotFound) = r.find() {
+        event!(Level::WARN, "Requested question was not found");
+        Ok(warp::reply::with_status(
+            "Question not found".to_string(),
+            StatusCode::NOT_FOUND,
+        )
+        .into_response())
+    } else if let Some(crate::Error::AnswerNotFound) = r.find() {
+        event!(Level::WARN, "Requested answer was not found");
+        Ok(warp::reply::with_status(
+            "Answer not found".to_string(),
+            StatusCode::NOT_FOUND,
+        )
+        .into_response())
+    } else if let Some(crate::Error::AccountNotFound) = r.find() {
+        event!(Level::WARN, "Requested account was not found");
+        Ok(warp::reply::with_status(
+            "Account not found".to_string(),
+            StatusCode::NOT_FOUND,
+        )
+        .into_response())
+    } else if let Some(crate::Error::ApiKeyNotFound) = r.find() {
+        event!(Level::WARN, "Requested API key was not found");
+        Ok(warp::reply::with_status(
+            "API key not found".to_string(),
+            StatusCode::NOT_FOUND,
+        )
+        .into_response())
+    } else if let Some(crate::Error::DuplicateQuestion(id)) = r.find() {
+        event!(Level::WARN, "Similar question already exists: {}", id);
+        // 기존 질문을 바로 조회할 수 있도록 Location 헤더로 주소를 알려준다.
+        Ok(warp::reply::with_header(
+            warp::reply::with_status(
+                format!("Similar question already exists: {}", id),
+                StatusCode::CONFLICT,
+            ),
+            "Location",
+            format!("/questions/{}", id),
+        )
+        .into_response())
+    } else if let Some(crate::Error::AnswerConflict) = r.find() {
+        event!(Level::WARN, "Answer already exists");
+        Ok(warp::reply::with_status(
+            "Answer already exists".to_string(),
+            StatusCode::CONFLICT,
+        )
+        .into_response())
+    } else if let Some(crate::Error::EmailTaken) = r.find() {
+        event!(Level::WARN, "Email is already in use");
+        Ok(warp::reply::with_status(
+            "Email is already in use".to_string(),
+            StatusCode::CONFLICT,
+        )
+        .into_response())
+    } else if let Some(crate::Error::EmailNotVerified) = r.find() {
+        event!(Level::WARN, "Write request from an account with an unverified email");
+        Ok(warp::reply::with_status(
+            "Email address is not verified".to_string(),
+            StatusCode::FORBIDDEN,
+        )
+        .into_response())
+    } else if let Some(crate::Error::InvalidStatusTransition(from, to)) = r.find() {
+        event!(Level::WARN, "Invalid question status transition: {} -> {}", from, to);
+        Ok(warp::reply::with_status(
+            format!("Cannot change question status from {} to {}", from, to),
+            StatusCode::CONFLICT,
+        )
+        .into_response())
+    } else if let Some(crate::Error::RangeNotSatisfiable) = r.find() {
+        event!(Level::WARN, "Requested range not satisfiable");
+        Ok(warp::reply::with_header(
+            warp::reply::with_status(
+                "Requested range not satisfiable".to_string(),
+                StatusCode::RANGE_NOT_SATISFIABLE,
+            ),
+            "Content-Range",
+            "items */*",
+        )
+        .into_response())
     } else if let Some(crate::Error::WrongPassword) = r.find() {
         event!(Level::ERROR, "Entered wrong password");
         Ok(warp::reply::with_status(
             "Wrong E-Mail/Password combination".to_string(),
             StatusCode::UNAUTHORIZED,
-        ))
+        )
+        .into_response())
     } else if let Some(crate::Error::MiddlewareReqwestAPIError(e)) = r.find() {
+        // reqwest 에러는 From 구현에서 ReqwestAPIError로 옮겨지므로 여기에는 미들웨어 자체의 에러만 온다.
         event!(Level::ERROR, "{}", e);
         Ok(warp::reply::with_status(
             "Internal Server Error".to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        )
+        .into_response())
     } else if let Some(crate::Error::ClientError(e)) = r.find() {
         event!(Level::ERROR, "{}", e);
         Ok(warp::reply::with_status(
             "Internal Server Error".to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        )
+        .into_response())
     } else if let Some(crate::Error::ServerError(e)) = r.find() {
+        // 외부 API의 5xx 에러는 우리 서버의 문제가 아니므로 502로 알려 클라이언트가 재시도할 수 있게 한다.
         event!(Level::ERROR, "{}", e);
+        Ok(warp::reply::with_status(
+            "Bad Gateway".to_string(),
+            StatusCode::BAD_GATEWAY,
+        )
+        .into_response())
+    } else if let Some(crate::Error::MissingEnvVar(name)) = r.find() {
+        event!(Level::ERROR, "Environment variable not set: {}", name);
+        Ok(warp::reply::with_status(
+            "Internal Server Error".to_string(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+        .into_response())
+    } else if let Some(crate::Error::TaskFailed(reason)) = r.find() {
+        event!(Level::ERROR, "Background task failed: {}", reason);
         Ok(warp::reply::with_status(
             "Internal Server Error".to_string(),
             StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        )
+        .into_response())
+    } else if let Some(error) = r.find::<PayloadTooLarge>() {
+        event!(Level::WARN, "{}", error);
+        Ok(json_error("Payload too large", StatusCode::PAYLOAD_TOO_LARGE))
+    } else if let Some(error) = r.find::<LengthRequired>() {
+        event!(Level::WARN, "{}", error);
+        Ok(json_error("Content-Length required", StatusCode::LENGTH_REQUIRED))
     } else if let Some(error) = r.find::<CorsForbidden>() {
         event!(Level::ERROR, "CORS forbidden error: {}", error);
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::FORBIDDEN,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<BodyDeserializeError>() {
-        event!(Level::ERROR, "Cannot deserialize request body: {}", error);
+        // serde 에러 메시지에는 내부 필드 이름이 들어 있으므로 자세한 내용은 디버그 로그로만 남긴다.
+        event!(Level::DEBUG, "Cannot deserialize request body: {}", error);
         Ok(warp::reply::with_status(
-            error.to_string(),
+            "Malformed request body".to_string(),
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )
+        .into_response())
     } else if let Some(error) = r.find::<Error>() {
         event!(Level::ERROR, "{}", error);
         Ok(warp::reply::with_status(
             error.to_string(),
             StatusCode::UNPROCESSABLE_ENTITY,
-        ))
+        )
+        .into_response())
     } else {
         event!(Level::WARN, "Requested route was not found");
-        Ok(warp::reply::with_status(
-            "Route not found".to_string(),
-            StatusCode::NOT_FOUND,
-        ))
+        // 다른 JSON 응답과 형식을 맞추기 위해 본문을 JSON으로 보낸다.
+        Ok(json_error("Route not found", StatusCode::NOT_FOUND))
     }
 }
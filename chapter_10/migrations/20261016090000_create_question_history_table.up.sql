@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS question_history (
+    id serial PRIMARY KEY,
+    question_id integer NOT NULL REFERENCES questions(id) ON DELETE CASCADE,
+    title VARCHAR(255) NOT NULL,
+    content TEXT NOT NULL,
+    tags TEXT [],
+    account_id integer NOT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
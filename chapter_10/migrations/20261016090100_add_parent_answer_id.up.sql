@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE answers
+ADD COLUMN parent_answer_id integer REFERENCES answers(id) ON DELETE CASCADE;
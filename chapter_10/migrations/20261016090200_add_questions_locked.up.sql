@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE questions
+ADD COLUMN locked BOOLEAN NOT NULL DEFAULT false;
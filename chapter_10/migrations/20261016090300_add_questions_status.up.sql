@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE questions
+ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'open'
+CHECK (status IN ('open', 'resolved', 'closed'));
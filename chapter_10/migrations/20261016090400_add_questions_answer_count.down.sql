@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS answers_answer_count ON answers;
+DROP FUNCTION IF EXISTS update_question_answer_count();
+
+ALTER TABLE questions
+DROP COLUMN answer_count;
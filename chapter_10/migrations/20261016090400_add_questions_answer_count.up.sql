@@ -0,0 +1,24 @@
+-- Add up migration script here
+ALTER TABLE questions
+ADD COLUMN answer_count integer NOT NULL DEFAULT 0;
+
+UPDATE questions q
+SET answer_count = (SELECT COUNT(*) FROM answers a WHERE a.corresponding_question = q.id);
+
+-- 답변이 추가되거나 삭제될 때 같은 트랜잭션 안에서 질문의 answer_count를 맞춘다.
+CREATE OR REPLACE FUNCTION update_question_answer_count() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE questions SET answer_count = answer_count + 1
+        WHERE id = NEW.corresponding_question;
+    ELSIF TG_OP = 'DELETE' THEN
+        UPDATE questions SET answer_count = answer_count - 1
+        WHERE id = OLD.corresponding_question;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER answers_answer_count
+AFTER INSERT OR DELETE ON answers
+FOR EACH ROW EXECUTE FUNCTION update_question_answer_count();
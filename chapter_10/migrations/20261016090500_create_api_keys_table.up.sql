@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS api_keys (
+    id serial PRIMARY KEY,
+    account_id integer NOT NULL,
+    key_hash VARCHAR(255) NOT NULL,
+    created_on TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_on TIMESTAMPTZ
+);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS questions_tags_idx;
+DROP INDEX IF EXISTS answers_corresponding_question_idx;
+DROP INDEX IF EXISTS questions_status_id_idx;
+DROP INDEX IF EXISTS questions_account_id_idx;
@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE INDEX IF NOT EXISTS questions_account_id_idx ON questions (account_id);
+
+-- 상태로 거른 목록도 id 순서대로 넘기므로 (status, id) 복합 인덱스를 둔다.
+CREATE INDEX IF NOT EXISTS questions_status_id_idx ON questions (status, id);
+
+-- 답변 테이블에서는 질문 id 열의 이름이 corresponding_question이다.
+CREATE INDEX IF NOT EXISTS answers_corresponding_question_idx ON answers (corresponding_question);
+
+-- 태그 배열 검색(tags @> ARRAY[...])에 사용한다.
+CREATE INDEX IF NOT EXISTS questions_tags_idx ON questions USING GIN (tags);
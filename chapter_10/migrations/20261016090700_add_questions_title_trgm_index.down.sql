@@ -0,0 +1,3 @@
+-- Add down migration script here
+-- pg_trgm 확장은 다른 곳에서도 쓸 수 있으므로 지우지 않고 인덱스만 지운다.
+DROP INDEX IF EXISTS questions_title_trgm_idx;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- 비슷한 제목의 질문을 찾을 때 similarity()를 사용한다.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS questions_title_trgm_idx ON questions USING GIN (title gin_trgm_ops);
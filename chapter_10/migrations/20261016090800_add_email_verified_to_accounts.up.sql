@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE accounts
+ADD COLUMN IF NOT EXISTS email_verified BOOLEAN NOT NULL DEFAULT false;
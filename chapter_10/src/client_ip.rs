@@ -0,0 +1,63 @@
+use std::net::{IpAddr, SocketAddr};
+
+use warp::Filter;
+use warp::http::HeaderMap;
+
+/// TRUST_PROXY=true일 때만 프록시가 넣어준 헤더를 믿는다.
+/// 기본값은 false로, 클라이언트가 헤더를 위조해 IP를 속이지 못하게 한다.
+pub fn trust_proxy() -> bool {
+    std::env::var("TRUST_PROXY").as_deref() == Ok("true")
+}
+
+/// 요청을 보낸 실제 클라이언트의 IP 주소를 찾는다.
+/// 프록시를 믿는 경우 X-Forwarded-For의 첫 번째 주소, X-Real-IP 순서로 확인하고
+/// 둘 다 없으면 연결된 원격 주소를 사용한다.
+pub fn resolve(
+    headers: &HeaderMap,
+    remote: Option<SocketAddr>,
+    trust_proxy: bool,
+) -> Option<IpAddr> {
+    if trust_proxy {
+        let forwarded_for = headers
+            .get("X-Forwarded-For")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .and_then(|value| value.trim().parse::<IpAddr>().ok());
+
+        if forwarded_for.is_some() {
+            return forwarded_for;
+        }
+
+        let real_ip = headers
+            .get("X-Real-IP")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<IpAddr>().ok());
+
+        if real_ip.is_some() {
+            return real_ip;
+        }
+    }
+
+    remote.map(|addr| addr.ip())
+}
+
+/// 연결된 원격 주소로, 서버가 요청 확장(extension)에 넣어 전달한다.
+/// hyper 서버에 warp::service로 올리면 warp::addr::remote()가 주소를 알 수 없기 때문이다.
+#[derive(Debug, Clone, Copy)]
+pub struct RemoteAddr(pub SocketAddr);
+
+/// 클라이언트 IP 주소를 추출하는 필터
+pub fn client_ip(
+    trust_proxy: bool,
+) -> impl Filter<Extract = (Option<IpAddr>,), Error = std::convert::Infallible> + Clone {
+    warp::header::headers_cloned()
+        .and(
+            warp::ext::get::<RemoteAddr>()
+                .map(|remote: RemoteAddr| Some(remote.0))
+                .or(warp::addr::remote())
+                .unify(),
+        )
+        .map(move |headers: HeaderMap, remote: Option<SocketAddr>| {
+            resolve(&headers, remote, trust_proxy)
+        })
+}
@@ -0,0 +1,399 @@
+use serde::de::DeserializeOwned;
+use std::future;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{Level, event};
+
+use warp::Filter;
+use warp::http::StatusCode;
+use warp::hyper::body::Bytes;
+use warp::path::FullPath;
+
+/// 요청 본문의 최대 크기(16KB)
+const MAX_BODY_SIZE: u64 = 1024 * 16;
+
+/// 본문 크기를 제한하고 JSON을 T로 역직렬화하는 필터
+/// 역직렬화에 실패하면 내부 필드 이름이 드러나지 않도록 Error::InvalidInput으로 바꾼다.
+/// 크기 제한 안에서도 깊게 중첩된 JSON은 파싱 비용이 크므로 max_depth(JSON_MAX_DEPTH, 기본 32)보다 깊으면 파싱하기 전에 거부한다.
+pub fn json_body<T: DeserializeOwned + Send>(
+    max_depth: usize,
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone {
+    warp::body::content_length_limit(MAX_BODY_SIZE)
+        .and(warp::body::bytes())
+        .and_then(move |body: Bytes| {
+            if json_depth_exceeds(&body, max_depth) {
+                event!(
+                    Level::DEBUG,
+                    "Request body nested deeper than {}",
+                    max_depth
+                );
+                return future::ready(Err(warp::reject::custom(
+                    handle_errors::Error::InvalidInput("JSON nesting is too deep".to_string()),
+                )));
+            }
+
+            future::ready(serde_json::from_slice::<T>(&body).map_err(|e| {
+                event!(Level::DEBUG, "Cannot deserialize request body: {}", e);
+                warp::reject::custom(handle_errors::Error::InvalidInput(
+                    "Malformed request body".to_string(),
+                ))
+            }))
+        })
+}
+
+// 문자열 안의 괄호는 건너뛰면서 배열과 객체의 중첩 깊이가 max_depth를 넘는지 확인한다.
+fn json_depth_exceeds(body: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for &byte in body {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    false
+}
+
+// 쿼리 매개변수 값에 들어 있으면 SQL 주입 시도로 의심하는 문자열
+const SQL_METACHARACTERS: &[&str] = &["'", ";", "--", "/*", "*/", "\0"];
+
+/// 쿼리 매개변수 값에 SQL 메타 문자가 들어 있는 요청을 찾아내는 필터
+/// 쿼리는 모두 바인딩 매개변수를 쓰므로 주입을 막는 용도가 아니라, 탐색 시도를 로그로 감시하기 위한 추가 방어선이다.
+/// SQL_PROBE_FILTER가 log이면 WARN 로그만 남기고, reject이면 400으로 거부하며, 그 밖의 값이나 없으면 아무것도 하지 않는다.
+/// 검색어의 작은따옴표(don't 등)도 걸리므로 reject는 로그로 오탐 여부를 확인한 뒤에 켠다.
+pub fn sql_probe_guard() -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    let mode = std::env::var("SQL_PROBE_FILTER").unwrap_or_default();
+    let (log, reject) = match mode.as_str() {
+        "log" => (true, false),
+        "reject" => (true, true),
+        _ => (false, false),
+    };
+
+    warp::path::full()
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+        .and_then(move |path: FullPath, query: String| {
+            if !log {
+                return future::ready(Ok(()));
+            }
+
+            let suspicious = suspicious_params(&query);
+            if suspicious.is_empty() {
+                return future::ready(Ok(()));
+            }
+
+            event!(
+                Level::WARN,
+                path = path.as_str(),
+                query = query.as_str(),
+                "Query parameters contain SQL metacharacters: {:?}",
+                suspicious
+            );
+
+            if reject {
+                future::ready(Err(warp::reject::custom(
+                    handle_errors::Error::SuspiciousQuery(suspicious),
+                )))
+            } else {
+                future::ready(Ok(()))
+            }
+        })
+        .untuple_one()
+}
+
+// 퍼센트 인코딩을 푼 값에 SQL 메타 문자가 있는 매개변수의 이름을 정렬해 돌려준다.
+fn suspicious_params(query: &str) -> Vec<String> {
+    let mut names: Vec<String> = query
+        .split('&')
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = value.replace('+', " ");
+            let value = urlencoding::decode(&value)
+                .map(|decoded| decoded.into_owned())
+                .unwrap_or(value);
+
+            if SQL_METACHARACTERS
+                .iter()
+                .any(|pattern| value.contains(pattern))
+            {
+                Some(
+                    urlencoding::decode(name)
+                        .map(|decoded| decoded.into_owned())
+                        .unwrap_or_else(|_| name.to_string()),
+                )
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// TLS를 끝내는 프록시 뒤에서 HTTP로 들어온 요청을 HTTPS로 보내는 필터
+/// FORCE_HTTPS=true일 때만 동작하며, X-Forwarded-Proto가 http이면 같은 주소의 https로 308 리디렉션한다.
+/// FORCE_HTTPS_REJECT=true이면 리디렉션하지 않고 403으로 거부한다.
+/// 프록시를 거치지 않아 헤더가 없는 요청(내부 헬스 체크 등)은 그대로 통과시킨다.
+pub fn require_https() -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    let force_https = std::env::var("FORCE_HTTPS").as_deref() == Ok("true");
+    let reject = std::env::var("FORCE_HTTPS_REJECT").as_deref() == Ok("true");
+
+    warp::header::optional::<String>("x-forwarded-proto")
+        .and(warp::header::optional::<String>("host"))
+        .and(warp::path::full())
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+        .and_then(
+            move |proto: Option<String>, host: Option<String>, path: FullPath, query: String| {
+                // 프록시를 여러 번 거치면 쉼표로 이어지므로 클라이언트와 맞닿은 첫 번째 값을 본다.
+                let is_http = proto
+                    .map(|value| {
+                        value
+                            .split(',')
+                            .next()
+                            .unwrap_or("")
+                            .trim()
+                            .eq_ignore_ascii_case("http")
+                    })
+                    .unwrap_or(false);
+
+                if !force_https || !is_http {
+                    return future::ready(Ok(()));
+                }
+
+                // Host 헤더가 없으면 리디렉션할 주소를 만들 수 없으므로 거부한다.
+                let location = host.filter(|_| !reject).map(|host| {
+                    let mut location = format!("https://{}{}", host, path.as_str());
+                    if !query.is_empty() {
+                        location.push('?');
+                        location.push_str(&query);
+                    }
+                    location
+                });
+
+                future::ready(Err(warp::reject::custom(
+                    handle_errors::Error::HttpsRequired(location),
+                )))
+            },
+        )
+        .untuple_one()
+}
+
+/// 모든 경로에서 받는 디버깅용 쿼리 매개변수로, 핸들러는 이 값을 무시해야 한다.
+pub const PRETTY_PARAM: &str = "pretty";
+
+/// ?pretty=true이면 JSON 응답 본문을 들여쓰기한 형태(serde_json::to_vec_pretty)로 바꾸는 필터
+/// curl로 디버깅할 때 읽기 쉽게 하려는 용도로, 기본값은 지금처럼 한 줄로 압축된 JSON이다.
+/// 본문을 serde_json::Value로 다시 읽으므로 객체의 키는 이름 순으로 정렬된다.
+/// 핸들러마다 플래그를 넘기지 않도록 경로 전체에 한 번 씌운다: filters::pretty_json(routes)
+pub fn pretty_json<F, R>(
+    filter: F,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = warp::Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    R: warp::Reply + Send,
+{
+    warp::query::raw()
+        .or(warp::any().map(String::new))
+        .unify()
+        .map(|query: String| {
+            query
+                .split('&')
+                .any(|pair| pair.split_once('=') == Some((PRETTY_PARAM, "true")))
+        })
+        .and(filter)
+        .and_then(|pretty: bool, reply: R| async move {
+            let response = reply.into_response();
+            let is_json = response
+                .headers()
+                .get("content-type")
+                .map(|value| value.as_bytes().starts_with(b"application/json"))
+                .unwrap_or(false);
+            if !pretty || !is_json {
+                return Ok::<_, warp::Rejection>(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            // 핸들러의 JSON 응답은 메모리에 있는 본문이므로 읽기에 실패하지 않는다.
+            let body = match warp::hyper::body::to_bytes(body).await {
+                Ok(body) => body,
+                Err(e) => {
+                    event!(Level::ERROR, "Cannot read response body: {}", e);
+                    Bytes::new()
+                }
+            };
+
+            // JSON으로 읽을 수 없는 본문은 그대로 돌려준다.
+            let body = serde_json::from_slice::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|value| serde_json::to_vec_pretty(&value).ok())
+                .map(Bytes::from)
+                .unwrap_or(body);
+
+            Ok(warp::reply::Response::from_parts(parts, body.into()))
+        })
+}
+
+/// 동시에 처리 중인 요청 수를 semaphore의 permit 수로 제한한다.
+/// 남은 permit이 없으면 기다리지 않고 503으로 거부해, 요청이 몰릴 때 데이터베이스 연결이 바닥나지 않게 한다.
+/// permit은 filter가 응답을 만들 때까지 들고 있다가 놓는다.
+/// 전체 경로에 한 번 씌운다: filters::concurrency_limit(semaphore, routes)
+pub fn concurrency_limit<F, R>(
+    semaphore: Arc<Semaphore>,
+    filter: F,
+) -> impl Filter<Extract = (R,), Error = warp::Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    R: warp::Reply + Send,
+{
+    warp::any()
+        .and_then(move || {
+            future::ready(
+                semaphore
+                    .clone()
+                    .try_acquire_owned()
+                    .map_err(|_| warp::reject::custom(handle_errors::Error::ServiceUnavailable)),
+            )
+        })
+        .and(filter)
+        .map(|_permit: OwnedSemaphorePermit, reply: R| reply)
+}
+
+/// enabled가 false이면 경로가 없는 것처럼 404로 거부하는 필터
+pub fn enabled(enabled: bool) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::any()
+        .and_then(move || {
+            if enabled {
+                future::ready(Ok(()))
+            } else {
+                future::ready(Err(warp::reject::not_found()))
+            }
+        })
+        .untuple_one()
+}
+
+/// Content-Type이 application/json인 요청만 통과시키는 필터
+/// JSON 본문을 받는 POST/PUT 경로에만 붙이고, GET/DELETE에는 붙이지 않는다.
+/// 헤더가 없거나 다르면 역직렬화 단계의 422 대신 415를 반환한다.
+pub fn json_content_type() -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("content-type")
+        .and_then(|content_type: Option<String>| {
+            let is_json = content_type
+                .map(|value| {
+                    value.split(';').next().unwrap_or("").trim().to_lowercase()
+                        == "application/json"
+                })
+                .unwrap_or(false);
+
+            if is_json {
+                future::ready(Ok(()))
+            } else {
+                future::ready(Err(warp::reject::custom(
+                    handle_errors::Error::UnsupportedMediaType,
+                )))
+            }
+        })
+        .untuple_one()
+}
+
+/// Accept 헤더가 JSON 응답을 받을 수 있는 요청만 통과시키는 필터
+/// 헤더가 없거나 */*, application/*, application/json 중 하나가 있으면 통과하고, 아니면 406으로 거부한다.
+pub fn accepts_json() -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("accept")
+        .and_then(|accept: Option<String>| {
+            let acceptable = accept
+                .map(|value| {
+                    value.split(',').any(|item| {
+                        let media_type = item.split(';').next().unwrap_or("").trim().to_lowercase();
+                        matches!(
+                            media_type.as_str(),
+                            "*/*" | "application/*" | "application/json"
+                        )
+                    })
+                })
+                .unwrap_or(true);
+
+            if acceptable {
+                future::ready(Ok(()))
+            } else {
+                future::ready(Err(warp::reject::custom(
+                    handle_errors::Error::NotAcceptable,
+                )))
+            }
+        })
+        .untuple_one()
+}
+
+/// 클라이언트의 Accept-Encoding 헤더에 주어진 인코딩이 있을 때만 통과하는 필터
+/// warp의 압축 필터는 헤더와 관계없이 항상 압축하므로, 이 필터로 먼저 걸러낸다.
+pub fn accepts_encoding(
+    encoding: &'static str,
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("accept-encoding")
+        .and_then(move |header: Option<String>| {
+            let accepted = header
+                .map(|value| {
+                    value
+                        .split(',')
+                        .any(|item| item.split(';').next().unwrap_or("").trim() == encoding)
+                })
+                .unwrap_or(false);
+
+            if accepted {
+                future::ready(Ok(()))
+            } else {
+                future::ready(Err(warp::reject::reject()))
+            }
+        })
+        .untuple_one()
+}
+
+/// 경로의 기준 형태는 끝에 슬래시가 없는 것(/questions)이다.
+/// /questions/처럼 슬래시로 끝나는 요청은 슬래시를 뗀 주소로 308 리디렉션한다.
+/// 308은 메서드와 본문을 그대로 유지하므로 POST, PUT 요청도 다시 보내진다.
+/// 다른 경로가 모두 거부한 뒤에만 시도되도록 경로 목록의 마지막에 붙인다.
+pub fn trailing_slash_redirect()
+-> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path::full()
+        .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+        .and_then(|path: FullPath, query: String| {
+            let path = path.as_str();
+            if path.len() <= 1 || !path.ends_with('/') {
+                return future::ready(Err(warp::reject::not_found()));
+            }
+
+            let mut location = match path.trim_end_matches('/') {
+                "" => "/".to_string(),
+                trimmed => trimmed.to_string(),
+            };
+            if !query.is_empty() {
+                location.push('?');
+                location.push_str(&query);
+            }
+
+            future::ready(Ok(warp::reply::with_status(
+                warp::reply::with_header(warp::reply(), "Location", location),
+                StatusCode::PERMANENT_REDIRECT,
+            )))
+        })
+}
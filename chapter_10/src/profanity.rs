@@ -1,8 +1,14 @@
-use reqwest_middleware::ClientBuilder;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{RetryTransientMiddleware, policies::ExponentialBackoff};
 use serde::{Deserialize, Serialize};
 
+use std::collections::HashSet;
 use std::env;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct APIResponse {
@@ -27,23 +33,208 @@ struct BadWordsResponse {
     censored_content: String,
 }
 
-pub async fn check_profanity(content: String) -> Result<String, handle_errors::Error> {
-    // ENV VARIABLE이 설정되었는지 main.rs에서 이미 확인했다.
-    // 그러니 여기에서는 unwrap()을 해도 안전하다.
-    let api_key = env::var("BAD_WORDS_API_KEY").unwrap();
+// 재시도 간격이 지수적으로 늘어날 때의 상한
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
 
-    let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
-    let client = ClientBuilder::new(reqwest::Client::new())
-        .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-        .build();
+/// APILayer를 호출하는 HTTP 클라이언트로, 서버를 띄울 때 한 번 만들어 복제해서 쓴다.
+#[derive(Clone)]
+pub struct ApiLayerClient {
+    client: ClientWithMiddleware,
+}
+
+impl ApiLayerClient {
+    /// 재시도 횟수(PROFANITY_MAX_RETRIES)와 첫 재시도 대기 시간(PROFANITY_RETRY_BASE_MS)으로 재시도 정책을 만든다.
+    /// 재시도를 0으로 두면 바로 실패한다.
+    /// 호출 한 번을 기다리는 시간은 PROFANITY_TIMEOUT_SECONDS로, 넘으면 504로 응답한다.
+    pub fn new(max_retries: u32, base_delay: Duration, timeout: Duration) -> Self {
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(base_delay, base_delay.max(MAX_RETRY_DELAY))
+            .build_with_max_retries(max_retries);
+        // 제한 시간이 없으면 외부 API가 응답하지 않을 때 요청이 끝나지 않는다.
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to build APILayer HTTP client");
+        let client = ClientBuilder::new(client)
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        ApiLayerClient { client }
+    }
+}
+
+impl fmt::Debug for ApiLayerClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApiLayerClient").finish()
+    }
+}
+
+/// 검사에서 찾아낸 금칙어 하나
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedWord {
+    pub word: String,
+    pub original: String, // 내용에 실제로 쓰인 형태
+    pub deviations: i64,
+}
+
+/// 가린 내용과 함께 어떤 금칙어가 걸렸는지 담은 검사 결과
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfanityReport {
+    pub censored_content: String,
+    pub bad_words_total: i64,
+    pub bad_words: Vec<DetectedWord>,
+}
+
+pub type CheckFuture = Pin<Box<dyn Future<Output = Result<String, handle_errors::Error>> + Send>>;
+
+/// 금칙어 검사 함수를 감싼 타입으로, store_filter처럼 필터로 경로 핸들러에 전달한다.
+/// 실제 서버에서는 APILayer를 호출하고, 테스트에서는 네트워크 없이 동작하는 함수로 바꿔 넣을 수 있다.
+/// routes::question의 tests 모듈이 이 방법으로 add_question을 테스트한다.
+/// # 사용 예
+/// ```rust,ignore
+/// // 입력을 그대로 돌려주는 검사기
+/// let profanity = ProfanityChecker::passthrough();
+/// // 외부 API 에러를 흉내 내는 검사기
+/// let failing = ProfanityChecker::new(|_| async {
+///     Err(handle_errors::Error::ServerError(handle_errors::APILayerError {
+///         status: 503,
+///         message: "Service Unavailable".to_string(),
+///     }))
+/// });
+/// let reply = routes::question::add_question(session, store, profanity, params, new_question).await;
+/// ```
+#[derive(Clone)]
+pub struct ProfanityChecker {
+    check: Arc<dyn Fn(String) -> CheckFuture + Send + Sync>,
+}
+
+impl ProfanityChecker {
+    pub fn new<F, Fut>(check: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, handle_errors::Error>> + Send + 'static,
+    {
+        ProfanityChecker {
+            check: Arc::new(move |content| Box::pin(check(content))),
+        }
+    }
+
+    /// APILayer의 bad_words API로 검사한다.
+    /// max_bad_words(PROFANITY_MAX_BAD_WORDS)가 있으면 금칙어가 그보다 많은 내용은 가리지 않고 거부한다.
+    /// PROFANITY_WORDLIST_PATH의 로컬 금칙어 목록에 있는 단어가 있으면 API를 부르지 않고 바로 거부한다.
+    pub fn apilayer(client: ApiLayerClient, max_bad_words: Option<i64>) -> Self {
+        let banned_words = Arc::new(load_banned_words());
+
+        ProfanityChecker::new(move |content| {
+            let banned_words = banned_words.clone();
+            let client = client.clone();
+            async move {
+                if contains_banned_word(&content, &banned_words) {
+                    return Err(handle_errors::Error::BannedWord);
+                }
+                check_profanity(&client, content, max_bad_words).await
+            }
+        })
+    }
+
+    /// 내용을 바꾸지 않고 그대로 돌려준다.
+    pub fn passthrough() -> Self {
+        ProfanityChecker::new(|content| async move { Ok(content) })
+    }
+
+    pub async fn check(&self, content: String) -> Result<String, handle_errors::Error> {
+        (self.check)(content).await
+    }
+}
+
+impl fmt::Debug for ProfanityChecker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProfanityChecker").finish()
+    }
+}
+
+/// PROFANITY_WORDLIST_PATH 파일에서 로컬 금칙어 목록을 읽는다.
+/// 한 줄에 한 단어씩 적고, 빈 줄과 #으로 시작하는 줄은 무시한다. 대소문자는 구분하지 않는다.
+/// 설정하지 않으면 빈 목록을 반환해 모든 내용을 API로 검사한다.
+fn load_banned_words() -> HashSet<String> {
+    match env::var("PROFANITY_WORDLIST_PATH") {
+        Ok(path) => std::fs::read_to_string(&path)
+            .expect("Cannot read PROFANITY_WORDLIST_PATH")
+            .lines()
+            .map(|line| line.trim().to_lowercase())
+            .filter(|word| !word.is_empty() && !word.starts_with('#'))
+            .collect(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+// 글자와 숫자가 아닌 문자로 단어를 나눠 목록에 있는 단어가 하나라도 있는지 확인한다.
+fn contains_banned_word(content: &str, banned_words: &HashSet<String>) -> bool {
+    if banned_words.is_empty() {
+        return false;
+    }
+
+    content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .any(|word| banned_words.contains(&word.to_lowercase()))
+}
+
+pub async fn check_profanity(
+    client: &ApiLayerClient,
+    content: String,
+    max_bad_words: Option<i64>,
+) -> Result<String, handle_errors::Error> {
+    let res = request_bad_words(client, content).await?;
+
+    match max_bad_words {
+        // 금칙어가 너무 많으면 모두 가린 내용을 저장하는 대신 요청을 거부한다.
+        Some(max) if res.bad_words_total > max => Err(handle_errors::Error::TooMuchProfanity(max)),
+        _ => Ok(res.censored_content),
+    }
+}
+
+/// check_profanity와 같은 API를 호출하지만 걸린 금칙어 목록도 함께 반환한다.
+/// 질문/답변 저장에는 쓰지 않고 관리자 미리보기에서만 사용한다.
+pub async fn check_profanity_details(
+    client: &ApiLayerClient,
+    content: String,
+) -> Result<ProfanityReport, handle_errors::Error> {
+    let res = request_bad_words(client, content).await?;
+
+    Ok(ProfanityReport {
+        censored_content: res.censored_content,
+        bad_words_total: res.bad_words_total,
+        bad_words: res
+            .bad_words_list
+            .into_iter()
+            .map(|bad_word| DetectedWord {
+                word: bad_word.word,
+                original: bad_word.original,
+                deviations: bad_word.deviations,
+            })
+            .collect(),
+    })
+}
+
+async fn request_bad_words(
+    client: &ApiLayerClient,
+    content: String,
+) -> Result<BadWordsResponse, handle_errors::Error> {
+    // ENV VARIABLE이 설정되지 않았다면 패닉 대신 에러를 반환해 500으로 처리되도록 한다.
+    let api_key = env::var("BAD_WORDS_API_KEY")
+        .map_err(|_| handle_errors::Error::MissingEnvVar("BAD_WORDS_API_KEY".to_string()))?;
 
     let res = client
+        .client
         .post("https://api.apilayer.com/bad_words?censor_character=*") // post 메서드는 HTTP POST를 보내며 URL로 &str을 받는다.
         .header("apikey", api_key)
         .body(content) // 본문에는 금칙 단어를 검사할 내용을 담는다.
         .send()
         .await // send 메서드는 비동기이며 에러를 반환할 수 있으므로 .await와 ?를 뒤에 붙인다.
-        .map_err(|e| handle_errors::Error::MiddlewareReqwestAPIError(e))?;
+        .map_err(handle_errors::Error::from)?;
 
     if !res.status().is_success() {
         // 응답 상태가 성공인지 검사한다.
@@ -57,15 +248,21 @@ pub async fn check_profanity(content: String) -> Result<String, handle_errors::E
         }
     }
     match res.json::<BadWordsResponse>().await {
-        Ok(res) => Ok(res.censored_content),
-        Err(e) => Err(handle_errors::Error::ReqwestAPIError(e)),
+        Ok(res) => Ok(res),
+        Err(e) => Err(handle_errors::Error::from(e)),
     }
 }
 
 async fn transform_error(res: reqwest::Response) -> handle_errors::APILayerError {
     // 응답 값을 받아 (이 시점에서는 우리는 해당 값이 에러임을 안다) 해당 메시지에 상태 코드 값을 추가한다.
-    handle_errors::APILayerError {
-        status: res.status().as_u16(),
-        message: res.json::<APIResponse>().await.unwrap().message,
-    }
+    let status = res.status().as_u16();
+    let body = res.text().await.unwrap_or_default();
+
+    // 본문이 {"message": ...} 형태가 아니라면(HTML 에러 페이지 등) 원본 텍스트를 그대로 메시지로 사용한다.
+    let message = match serde_json::from_str::<APIResponse>(&body) {
+        Ok(res) => res.message,
+        Err(_) => body,
+    };
+
+    handle_errors::APILayerError { status, message }
 }
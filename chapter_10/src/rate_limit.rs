@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::future;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use warp::Filter;
+
+use crate::client_ip::client_ip;
+use crate::types::account::{AccountId, Session};
+
+/// 키(IP 주소, 계정 ID 등)마다 정해진 시간 동안 허용할 요청 수를 세는 고정 윈도 방식의 제한기
+#[derive(Debug, Clone)]
+pub struct RateLimiter<K> {
+    max_requests: u32,
+    window: Duration,
+    counters: Arc<Mutex<HashMap<K, (Instant, u32)>>>, // 키마다 윈도 시작 시각과 요청 수를 저장한다.
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        RateLimiter {
+            max_requests,
+            window,
+            counters: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 요청을 하나 기록하고, 허용 범위 안이면 true를 반환한다.
+    pub fn check(&self, key: K) -> bool {
+        let now = Instant::now();
+        let mut counters = self.counters.lock().unwrap();
+
+        // 윈도가 지난 항목은 지워서 맵이 끝없이 커지지 않게 한다.
+        counters.retain(|_, (started, _)| now.duration_since(*started) < self.window);
+
+        let (_, count) = counters.entry(key).or_insert((now, 0));
+        *count += 1;
+        *count <= self.max_requests
+    }
+}
+
+/// 요청한 클라이언트의 IP 주소를 기준으로 요청 수를 제한하는 필터
+pub fn by_ip(
+    limiter: RateLimiter<IpAddr>,
+    trust_proxy: bool,
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    client_ip(trust_proxy)
+        .and_then(move |ip: Option<IpAddr>| {
+            let allowed = match ip {
+                Some(ip) => limiter.check(ip),
+                None => true, // 주소를 알 수 없는 경우(테스트 등)에는 제한하지 않는다.
+            };
+
+            if allowed {
+                future::ready(Ok(()))
+            } else {
+                future::ready(Err(warp::reject::custom(
+                    handle_errors::Error::TooManyRequests,
+                )))
+            }
+        })
+        .untuple_one()
+}
+
+/// 로그인한 계정을 기준으로 요청 수를 제한한다.
+/// auth() 뒤에 and_then으로 붙여 세션을 그대로 다음 필터에 넘긴다.
+/// auth().and_then(rate_limit::by_account(limiter.clone()))
+pub fn by_account(
+    limiter: RateLimiter<AccountId>,
+) -> impl Fn(Session) -> future::Ready<Result<Session, warp::Rejection>> + Clone {
+    move |session: Session| {
+        if limiter.check(session.account_id.clone()) {
+            future::ready(Ok(session))
+        } else {
+            future::ready(Err(warp::reject::custom(
+                handle_errors::Error::TooManyRequests,
+            )))
+        }
+    }
+}
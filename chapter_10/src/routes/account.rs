@@ -0,0 +1,10 @@
+use crate::store::Store;
+use crate::types::account::AccountId;
+
+// 질문 수와 답변 수는 공개 정보이므로 인증 없이 조회할 수 있다.
+pub async fn get_account_stats(id: i32, store: Store) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.get_account_stats(AccountId(id)).await {
+        Ok(stats) => Ok(warp::reply::json(&stats)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use crate::filters::PRETTY_PARAM;
+use crate::routes::authentication::is_admin;
+use crate::store::Store;
+use crate::types::account::Session;
+use crate::types::pagination::{Pagination, extract_pagination, reject_unknown_params};
+
+// 대량으로 가져온 뒤처럼 태그 데이터가 어긋났을 때 태그를 정리하고 태그별 질문 수를 다시 센다. 관리자만 사용할 수 있다.
+pub async fn recount_tags(
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !is_admin(&session.account_id) {
+        return Err(warp::reject::custom(handle_errors::Error::Unauthorized));
+    }
+
+    match store.recount_tags().await {
+        Ok(recount) => Ok(warp::reply::json(&recount)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// 모든 질문에 달린 답변을 최근 순으로 반환한다. 관리자만 사용할 수 있다.
+/// sort는 created_on(기본, 최근 것부터)이나 id를 받는다.
+/// 예: /admin/answers?limit=50&offset=0&sort=created_on
+pub async fn get_all_answers(
+    session: Session,
+    mut params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !is_admin(&session.account_id) {
+        return Err(warp::reject::custom(handle_errors::Error::Unauthorized));
+    }
+
+    reject_unknown_params(&params, &["limit", "offset", "sort"])?;
+    params.remove(PRETTY_PARAM);
+
+    let order_by = match params.remove("sort").as_deref() {
+        None | Some("created_on") => "an.created_on DESC, an.id DESC",
+        Some("id") => "an.id",
+        Some(sort) => {
+            return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+                format!("sort must be created_on or id: {}", sort),
+            )));
+        }
+    };
+
+    let mut pagination = Pagination::default();
+    if !params.is_empty() {
+        pagination = extract_pagination(params)?;
+    }
+    pagination.apply_page_size()?;
+
+    match store
+        .get_all_answers(pagination.limit, pagination.offset, order_by)
+        .await
+    {
+        Ok(answers) => Ok(warp::reply::json(&answers)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
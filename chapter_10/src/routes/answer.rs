@@ -1,28 +1,132 @@
 use warp::http::StatusCode;
 
-use crate::profanity::check_profanity;
+use crate::profanity::ProfanityChecker;
+use crate::sanitize::sanitize_html;
 use crate::store::Store;
 use crate::types::account::Session;
-use crate::types::answer::NewAnswer;
+use crate::types::answer::{AnswerUpdate, NewAnswer, validate_answer_length};
 
 pub async fn add_answer(
     session: Session,
     store: Store,
+    profanity: ProfanityChecker,
+    max_answers: i64,
     new_answer: NewAnswer,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let account_id = session.account_id;
-    let content = match check_profanity(new_answer.content).await {
+
+    // 빈 답변은 외부 API를 호출하기 전에 거른다.
+    if new_answer.content.trim().is_empty() {
+        return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+            "Answer content must not be empty".to_string(),
+        )));
+    }
+    validate_answer_length(&new_answer.content)?;
+
+    // 잠긴 질문에는 더 이상 답변을 달 수 없다.
+    if store.is_question_locked(&new_answer.question_id).await? {
+        return Err(warp::reject::custom(handle_errors::Error::QuestionLocked));
+    }
+
+    let answer_count = store
+        .count_answers_by_account_for_question(&new_answer.question_id, &account_id)
+        .await?;
+    if answer_count >= max_answers {
+        return Err(warp::reject::custom(handle_errors::Error::TooManyAnswers(
+            max_answers,
+        )));
+    }
+
+    // 답글이라면 부모 답변이 같은 질문에 달린 것인지 확인한다.
+    if let Some(parent_answer_id) = &new_answer.parent_answer_id {
+        match store.get_answer_question_id(parent_answer_id).await? {
+            Some(question_id) if question_id == new_answer.question_id => {}
+            _ => {
+                return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+                    "Parent answer does not belong to this question".to_string(),
+                )));
+            }
+        }
+    }
+
+    let content = match profanity.check(new_answer.content).await {
         Ok(res) => res,
         Err(e) => return Err(warp::reject::custom(e)),
     };
 
     let answer = NewAnswer {
-        content,
+        content: sanitize_html(content), // 금칙어 검사를 마친 내용에만 적용한다.
         question_id: new_answer.question_id,
+        parent_answer_id: new_answer.parent_answer_id,
     };
 
     match store.add_answer(answer, account_id).await {
-        Ok(_) => Ok(warp::reply::with_status("Answer added", StatusCode::OK)),
+        Ok(answer) => {
+            // 만든 답변을 돌려주고 Location 헤더로 답변을 다시 조회할 주소를 알려준다.
+            let location = format!("/answers/{}", answer.id.0);
+            Ok(warp::reply::with_header(
+                warp::reply::with_status(warp::reply::json(&answer), StatusCode::CREATED),
+                "Location",
+                location,
+            ))
+        }
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// 본문에 있는 필드만 바꾸며, 답변을 쓴 계정만 수정할 수 있다.
+pub async fn update_answer(
+    id: i32,
+    session: Session,
+    store: Store,
+    profanity: ProfanityChecker,
+    update: AnswerUpdate,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if update.is_empty() {
+        return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+            "No fields to update".to_string(),
+        )));
+    }
+
+    let content = match update.content {
+        Some(content) => {
+            if content.trim().is_empty() {
+                return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+                    "Answer content must not be empty".to_string(),
+                )));
+            }
+            validate_answer_length(&content)?;
+
+            match profanity.check(content).await {
+                Ok(res) => Some(sanitize_html(res)),
+                Err(e) => return Err(warp::reject::custom(e)),
+            }
+        }
+        None => None,
+    };
+
+    match store
+        .update_answer(id, AnswerUpdate { content }, session.account_id)
+        .await
+    {
+        Ok(answer) => Ok(warp::reply::json(&answer)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+pub async fn get_answer(id: i32, store: Store) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.get_answer(id).await? {
+        Some(answer) => Ok(warp::reply::json(&answer)),
+        None => Err(warp::reject::custom(handle_errors::Error::AnswerNotFound)),
+    }
+}
+
+pub async fn get_answers_for_question(
+    id: i32,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.get_answers_for_question(id).await {
+        Ok(answers) => Ok(warp::reply::json(&answers)),
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
@@ -0,0 +1,189 @@
+// 등록 → 로그인 → 질문 추가 → 수정 → 삭제로 이어지는 인증 흐름을 실제 데이터베이스로 끝까지 확인한다.
+// 데이터베이스(DATABASE_URL이나 DB_*, 기본 localhost:5432/rustwebdev)와 PASETO_KEY가 있어야 하므로
+// 기본으로는 건너뛰고 `cargo test auth_flow -- --ignored`로 실행한다.
+use warp::Filter;
+use warp::http::StatusCode;
+
+use crate::filters;
+use crate::profanity::ProfanityChecker;
+use crate::routes;
+use crate::store::{self, Store};
+
+async fn test_store() -> Store {
+    let db_url = store::database_url_from_env("localhost", 5432, "rustwebdev");
+    let store = Store::new(&db_url, None, 30000).await;
+    sqlx::migrate!()
+        .run(&store.connection)
+        .await
+        .expect("Cannot run migration");
+    store
+}
+
+// main.rs와 같은 필터로 흐름에 필요한 경로만 묶는다. 금칙어 검사는 외부 API 대신 그대로 통과시킨다.
+fn auth_flow_routes(
+    store: Store,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let write_auth = routes::authentication::write_auth(store.clone());
+    let store_filter = warp::any().map(move || store.clone());
+    let profanity = ProfanityChecker::passthrough();
+    let profanity_filter = warp::any().map(move || profanity.clone());
+
+    let registration = warp::post()
+        .and(warp::path("registration"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(routes::authentication::register);
+
+    let login = warp::post()
+        .and(warp::path("login"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and(warp::body::json())
+        .and_then(routes::authentication::login);
+
+    let add_question = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::end())
+        .and(write_auth.clone())
+        .and(store_filter.clone())
+        .and(profanity_filter.clone())
+        .and(warp::any().map(|| 80u32))
+        .and(warp::query())
+        .and(filters::json_body(32))
+        .and_then(routes::question::add_question);
+
+    let update_question = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(write_auth.clone())
+        .and(store_filter.clone())
+        .and(profanity_filter.clone())
+        .and(filters::json_body(32))
+        .and_then(routes::question::update_question);
+
+    let delete_question = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(write_auth)
+        .and(store_filter)
+        .and_then(routes::question::delete_question);
+
+    registration
+        .or(login)
+        .or(add_question)
+        .or(update_question)
+        .or(delete_question)
+        .recover(handle_errors::return_error)
+}
+
+// 매번 새 계정을 만들도록 실행마다 다른 이메일을 쓴다.
+async fn register_and_login<F>(routes: &F) -> String
+where
+    F: Filter + 'static,
+    F::Extract: warp::Reply + Send,
+{
+    let credentials = serde_json::json!({
+        "email": format!("{}@example.com", uuid::Uuid::new_v4()),
+        "password": "correct horse battery staple",
+    });
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/registration")
+        .json(&credentials)
+        .reply(routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/login")
+        .json(&credentials)
+        .reply(routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    format!("Bearer {}", body["token"].as_str().unwrap())
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres database and PASETO_KEY"]
+async fn auth_flow_enforces_question_ownership() {
+    let routes = auth_flow_routes(test_store().await);
+    let owner = register_and_login(&routes).await;
+    let other = register_and_login(&routes).await;
+
+    // 이전 실행에서 남은 비슷한 제목과 부딪히지 않도록 중복 검사를 끈다.
+    let response = warp::test::request()
+        .method("POST")
+        .path("/questions?allow_duplicates=true")
+        .header("Authorization", &owner)
+        .json(&serde_json::json!({
+            "title": "How do I test warp routes?",
+            "content": "I want to cover the whole auth flow.",
+            "tags": ["rust"],
+        }))
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    let id = body["question"]["id"].as_i64().unwrap();
+
+    let update = serde_json::json!({
+        "id": id,
+        "title": "How do I test warp routes end to end?",
+        "content": "I want to cover the whole auth flow.",
+        "tags": ["rust"],
+    });
+
+    // 다른 계정은 질문을 고치거나 지울 수 없다.
+    let response = warp::test::request()
+        .method("PUT")
+        .path(&format!("/questions/{}", id))
+        .header("Authorization", &other)
+        .json(&update)
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = warp::test::request()
+        .method("DELETE")
+        .path(&format!("/questions/{}", id))
+        .header("Authorization", &other)
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // 소유자는 고치고 지울 수 있다.
+    let response = warp::test::request()
+        .method("PUT")
+        .path(&format!("/questions/{}", id))
+        .header("Authorization", &owner)
+        .json(&update)
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["title"], "How do I test warp routes end to end?");
+
+    let response = warp::test::request()
+        .method("DELETE")
+        .path(&format!("/questions/{}", id))
+        .header("Authorization", &owner)
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // 이미 지운 질문은 없는 질문으로 처리된다.
+    let response = warp::test::request()
+        .method("DELETE")
+        .path(&format!("/questions/{}", id))
+        .header("Authorization", &owner)
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
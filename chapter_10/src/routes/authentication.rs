@@ -3,12 +3,15 @@ use chrono::prelude::*;
 
 use rand::Rng; // rand 크레이트의 도움을 받아 임의의 솔트를 만든다.
 
-use std::{env, future};
+use std::{collections::HashMap, env};
 use warp::Filter;
 use warp::http::StatusCode;
 
 use crate::store::Store;
-use crate::types::account::{Account, AccountId, Session}; // 토큰을 생성하는 데 사용하므로 AccountId를 임포트한다.
+use crate::types::account::{
+    Account, AccountId, EmailChange, EmailVerification, LoginResponse, NewApiKey,
+    PasswordResetConfirm, PasswordResetRequest, RegisterRequest, Session,
+}; // 토큰을 생성하는 데 사용하므로 AccountId를 임포트한다.
 
 pub fn verify_token(token: String) -> Result<Session, handle_errors::Error> {
     let key = env::var("PASETO_KEY").unwrap();
@@ -20,15 +23,23 @@ pub fn verify_token(token: String) -> Result<Session, handle_errors::Error> {
     )
     .map_err(|_| handle_errors::Error::CannotDecryptToken)?;
 
+    // 비밀번호 재설정이나 이메일 인증처럼 용도가 정해진 토큰은 로그인 세션으로 사용할 수 없다.
+    if token.get("purpose").is_some() {
+        return Err(handle_errors::Error::CannotDecryptToken);
+    }
+
     serde_json::from_value::<Session>(token).map_err(|_| handle_errors::Error::CannotDecryptToken)
 }
 
-pub async fn register(store: Store, account: Account) -> Result<impl warp::Reply, warp::Rejection> {
-    let hashed_password = hash_password(account.password.as_bytes()); // 비밀번호를 바이트 배열로 바꾼 후 새로 만든 해시 함수로 전달한다.
+pub async fn register(
+    store: Store,
+    request: RegisterRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let hashed_password = hash_password(request.password.as_bytes()); // 비밀번호를 바이트 배열로 바꾼 후 새로 만든 해시 함수로 전달한다.
 
     let account = Account {
-        id: account.id,
-        email: account.email,
+        id: None, // id는 데이터베이스가 정한다.
+        email: request.email,
         password: hashed_password, // 데이터베이스에 넣을 용도로 사용자가 입력한 비밀번호(평문) 대신 해시된(그리고 솔트를 추가한) 버전을 사용한다.
     };
 
@@ -38,6 +49,27 @@ pub async fn register(store: Store, account: Account) -> Result<impl warp::Reply
     }
 }
 
+pub async fn account_available(
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let email = match params.get("email") {
+        Some(email) => email.to_string(),
+        None => {
+            return Err(warp::reject::custom(
+                handle_errors::Error::MissingParameter("email".to_string()),
+            ));
+        }
+    };
+
+    match store.account_exists(email).await {
+        Ok(exists) => Ok(warp::reply::json(
+            &serde_json::json!({ "available": !exists }),
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
 pub fn hash_password(password: &[u8]) -> String {
     // 해시 함수는 문자열을 반환하며, 해당 문자열은 평문 비밀번호의 해시된 버전이다.
     let salt = rand::thread_rng().r#gen::<[u8; 32]>(); // rand 함수는 32바이트 크기의 난수를 만들어 슬라이스로 저장한다.
@@ -55,10 +87,12 @@ pub async fn login(store: Store, login: Account) -> Result<impl warp::Reply, war
                 // 검증 절차가 성공(라이브러리가 실패하지 않음)한 경우라면 다음을 실행한다.
                 if verified {
                     // 비밀번호가 실제로 확인되었는지 검사한다.
-                    Ok(warp::reply::json(&issue_token(
+                    Ok(warp::reply::json(&LoginResponse {
                         // 그리고 토큰을 만들어 AccountId에 넣는다.
-                        account.id.expect("id not found"),
-                    )))
+                        token: issue_token(account.id.expect("id not found")),
+                        token_type: "Bearer".to_string(),
+                        expires_in: TOKEN_LIFETIME_SECONDS,
+                    }))
                 } else {
                     Err(warp::reject::custom(handle_errors::Error::WrongPassword)) // 검증이 실패했다면 새로운 에러 타입인 WrongPassword를 만들고, 이를 이후에 handle-errors 크레이트에서 처리한다.
                 }
@@ -71,15 +105,73 @@ pub async fn login(store: Store, login: Account) -> Result<impl warp::Reply, war
     }
 }
 
+pub async fn change_email(
+    session: Session,
+    store: Store,
+    change: EmailChange,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let email = change.email.trim().to_string();
+    if !is_valid_email(&email) {
+        return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+            "Invalid email address".to_string(),
+        )));
+    }
+
+    // 토큰이 유출된 경우를 대비해 현재 비밀번호로 한 번 더 본인임을 확인한다.
+    let account = confirm_password(&store, &session.account_id, &change.password).await?;
+
+    if email != account.email && store.account_exists(email.clone()).await? {
+        return Err(warp::reject::custom(handle_errors::Error::EmailTaken));
+    }
+
+    match store.update_email(session.account_id, email).await {
+        Ok(_) => Ok(warp::reply::with_status("Email updated", StatusCode::OK)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// 계정의 현재 비밀번호가 맞는지 확인하고, 맞으면 계정을 반환한다.
+/// 이메일 변경이나 일괄 삭제처럼 되돌리기 어려운 요청에서 본인임을 한 번 더 확인할 때 사용한다.
+pub async fn confirm_password(
+    store: &Store,
+    account_id: &AccountId,
+    password: &str,
+) -> Result<Account, handle_errors::Error> {
+    let account = store.get_account_by_id(account_id).await?;
+    match verify_password(&account.password, password.as_bytes()) {
+        Ok(true) => Ok(account),
+        Ok(false) => Err(handle_errors::Error::WrongPassword),
+        Err(e) => Err(handle_errors::Error::ArgonLibraryError(e)),
+    }
+}
+
+// 로컬 부분과 도메인이 있고 공백이 없는지만 확인하는 간단한 검사
+fn is_valid_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains('@')
+                && !email.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
 fn verify_password(hash: &str, password: &[u8]) -> Result<bool, argon2::Error> {
     argon2::verify_encoded(hash, password) // argon2 크레이트는 해시의 일부인 솔트 값을 사용하여 데이터베이스의 해시가 로그인과정에서의 비밀번호와 일치하는지 검증한다.
 }
 
+// 로그인 토큰의 유효 기간(초)
+const TOKEN_LIFETIME_SECONDS: i64 = 60 * 60 * 24;
+
 fn issue_token(account_id: AccountId) -> String {
     let key = env::var("PASETO_KEY").unwrap();
 
     let current_date_time = Utc::now();
-    let dt = current_date_time + chrono::Duration::days(1);
+    let dt = current_date_time + chrono::Duration::seconds(TOKEN_LIFETIME_SECONDS);
 
     paseto::tokens::PasetoBuilder::new()
         .set_encryption_key(&Vec::from(key.as_bytes()))
@@ -90,13 +182,364 @@ fn issue_token(account_id: AccountId) -> String {
         .expect("Failed to construct paseto token w/ builder")
 }
 
+pub async fn verify(session: Session) -> Result<impl warp::Reply, warp::Rejection> {
+    // 클라이언트가 세션 만료까지 남은 시간을 보여줄 수 있도록 토큰의 시간 정보를 돌려준다.
+    let expires_in = session
+        .exp
+        .map(|exp| (exp - Utc::now()).num_seconds().max(0));
+
+    // 다른 응답과 같이 RFC3339 형식의 UTC 문자열로 돌려준다.
+    let rfc3339 = |time: DateTime<Utc>| time.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "accountId": session.account_id,
+        "exp": session.exp.map(rfc3339),
+        "nbf": session.nbf.map(rfc3339),
+        "expiresIn": expires_in,
+    })))
+}
+
+pub async fn create_api_key(
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // 키는 "<id>.<비밀 값>" 형식이다. 비밀 값은 비밀번호처럼 해시만 저장하고, id로 해시를 찾아 검증한다.
+    let secret = rand::thread_rng()
+        .r#gen::<[u8; 32]>()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    let key_hash = hash_password(secret.as_bytes());
+
+    match store.add_api_key(session.account_id, key_hash).await {
+        Ok(api_key) => Ok(warp::reply::with_status(
+            warp::reply::json(&NewApiKey {
+                id: api_key.id,
+                key: format!("{}.{}", api_key.id, secret),
+                created_on: api_key.created_on,
+            }),
+            StatusCode::CREATED,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+pub async fn revoke_api_key(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.revoke_api_key(id, &session.account_id).await {
+        Ok(true) => Ok(warp::reply::with_status("API key revoked", StatusCode::OK)),
+        Ok(false) => Err(warp::reject::custom(handle_errors::Error::ApiKeyNotFound)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+// 비밀번호 재설정 토큰에 넣는 용도(purpose) 클레임 값
+const PASSWORD_RESET_PURPOSE: &str = "password_reset";
+
+pub async fn request_password_reset(
+    store: Store,
+    request: PasswordResetRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // 계정이 있는지 여부를 응답으로 알 수 없도록 결과와 관계없이 같은 메시지를 돌려준다.
+    let account = match store.get_account(request.email).await {
+        Ok(account) => account,
+        Err(_) => {
+            return Ok(warp::reply::json(&serde_json::json!({
+                "message": "Password reset requested"
+            })));
+        }
+    };
+
+    // 재설정 토큰은 세션 토큰보다 훨씬 짧게 유지한다.
+    // 현재 비밀번호 해시의 지문을 넣어 두어 비밀번호가 한 번 바뀌면 같은 토큰을 다시 쓸 수 없게 한다.
+    let token = issue_purpose_token(
+        account.id.expect("id not found"),
+        PASSWORD_RESET_PURPOSE,
+        chrono::Duration::minutes(15),
+        Some(password_fingerprint(&account.password)),
+    );
+
+    // 아직 메일을 보내지 않으므로 개발 환경에서만 응답으로 토큰을 돌려준다.
+    if env::var("PASSWORD_RESET_RETURN_TOKEN").as_deref() == Ok("true") {
+        return Ok(warp::reply::json(&serde_json::json!({
+            "message": "Password reset requested",
+            "token": token
+        })));
+    }
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Password reset requested"
+    })))
+}
+
+pub async fn confirm_password_reset(
+    store: Store,
+    confirm: PasswordResetConfirm,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let claims = decode_purpose_token(confirm.token, PASSWORD_RESET_PURPOSE)
+        .map_err(warp::reject::custom)?;
+    let account_id = account_id_from_claims(&claims).map_err(warp::reject::custom)?;
+    let account = store.get_account_by_id(&account_id).await?;
+
+    // 토큰을 발급한 뒤 비밀번호가 바뀌었다면 이미 사용한 토큰이다.
+    if claims.get("fingerprint")
+        != Some(&serde_json::json!(password_fingerprint(&account.password)))
+    {
+        return Err(warp::reject::custom(
+            handle_errors::Error::CannotDecryptToken,
+        ));
+    }
+
+    let hashed_password = hash_password(confirm.password.as_bytes());
+
+    // 같은 토큰으로 동시에 들어온 요청 중 하나만 성공하도록 읽어 둔 해시가 그대로일 때만 바꾼다.
+    match store
+        .replace_password(account_id, &account.password, hashed_password)
+        .await
+    {
+        Ok(true) => Ok(warp::reply::with_status("Password updated", StatusCode::OK)),
+        Ok(false) => Err(warp::reject::custom(
+            handle_errors::Error::CannotDecryptToken,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+// 인코딩된 해시($argon2i$v=19$m=...,t=...,p=...$<솔트>$<해시>)에서 솔트 부분을 꺼낸다.
+// hash_password는 해시할 때마다 새 솔트를 만들므로 비밀번호가 바뀌면 지문도 바뀌고, 해시 자체는 토큰에 담지 않는다.
+fn password_fingerprint(hash: &str) -> &str {
+    hash.split('$').nth(4).unwrap_or_default()
+}
+
+// 이메일 인증 토큰에 넣는 용도(purpose) 클레임 값
+const EMAIL_VERIFICATION_PURPOSE: &str = "email_verification";
+
+/// 로그인한 계정의 이메일 인증 토큰을 발급한다.
+pub async fn request_email_verification(
+    session: Session,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // 메일을 열어 볼 시간을 고려해 재설정 토큰보다 길게 유지한다.
+    let token = issue_purpose_token(
+        session.account_id,
+        EMAIL_VERIFICATION_PURPOSE,
+        chrono::Duration::hours(24),
+        None,
+    );
+
+    // 비밀번호 재설정과 마찬가지로 메일을 보내기 전까지 개발 환경에서만 응답으로 토큰을 돌려준다.
+    if env::var("EMAIL_VERIFICATION_RETURN_TOKEN").as_deref() == Ok("true") {
+        return Ok(warp::reply::json(&serde_json::json!({
+            "message": "Email verification requested",
+            "token": token
+        })));
+    }
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "message": "Email verification requested"
+    })))
+}
+
+/// 이메일 인증 토큰을 확인하고 토큰에 담긴 계정의 이메일을 인증된 것으로 표시한다.
+pub async fn verify_email(
+    store: Store,
+    verification: EmailVerification,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = verify_purpose_token(verification.token, EMAIL_VERIFICATION_PURPOSE)
+        .map_err(warp::reject::custom)?;
+
+    match store.verify_email(&account_id).await {
+        Ok(true) => Ok(warp::reply::with_status("Email verified", StatusCode::OK)),
+        // 토큰은 유효하지만 그 사이에 계정이 삭제된 경우
+        Ok(false) => Err(warp::reject::custom(handle_errors::Error::AccountNotFound)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+// 비밀번호 재설정이나 이메일 인증처럼 한 가지 용도로만 쓸 수 있는 토큰을 발급한다.
+// fingerprint가 있으면 같은 이름의 클레임으로 넣어 토큰을 쓸 때 확인할 수 있게 한다.
+fn issue_purpose_token(
+    account_id: AccountId,
+    purpose: &str,
+    valid_for: chrono::Duration,
+    fingerprint: Option<&str>,
+) -> String {
+    let key = env::var("PASETO_KEY").unwrap();
+
+    let current_date_time = Utc::now();
+    let dt = current_date_time + valid_for;
+
+    let key = Vec::from(key.as_bytes());
+    let mut builder = paseto::tokens::PasetoBuilder::new();
+    builder
+        .set_encryption_key(&key)
+        .set_expiration(&dt)
+        .set_not_before(&Utc::now())
+        .set_claim("account_id", serde_json::json!(account_id))
+        .set_claim("purpose", serde_json::json!(purpose));
+    if let Some(fingerprint) = fingerprint {
+        builder.set_claim("fingerprint", serde_json::json!(fingerprint));
+    }
+
+    builder
+        .build()
+        .expect("Failed to construct paseto token w/ builder")
+}
+
+fn verify_purpose_token(token: String, purpose: &str) -> Result<AccountId, handle_errors::Error> {
+    account_id_from_claims(&decode_purpose_token(token, purpose)?)
+}
+
+// 토큰을 복호화하고 용도가 맞는지 확인한 뒤 클레임을 돌려준다.
+fn decode_purpose_token(
+    token: String,
+    purpose: &str,
+) -> Result<serde_json::Value, handle_errors::Error> {
+    let key = env::var("PASETO_KEY").unwrap();
+    let token = paseto::tokens::validate_local_token(
+        &token,
+        None,
+        key.as_bytes(),
+        &paseto::tokens::TimeBackend::Chrono,
+    )
+    .map_err(|_| handle_errors::Error::CannotDecryptToken)?;
+
+    // 로그인 세션 토큰이나 다른 용도의 토큰을 쓸 수 없도록 용도 클레임을 확인한다.
+    if token.get("purpose") != Some(&serde_json::json!(purpose)) {
+        return Err(handle_errors::Error::CannotDecryptToken);
+    }
+
+    Ok(token)
+}
+
+fn account_id_from_claims(claims: &serde_json::Value) -> Result<AccountId, handle_errors::Error> {
+    serde_json::from_value::<AccountId>(claims["account_id"].clone())
+        .map_err(|_| handle_errors::Error::CannotDecryptToken)
+}
+
+/// ADMIN_ACCOUNT_IDS(쉼표로 구분한 계정 id 목록)에 있는 계정인지 확인한다.
+/// 계정에 역할을 따로 저장하지 않으므로 관리자는 환경 변수로 지정한다.
+pub fn is_admin(account_id: &AccountId) -> bool {
+    env::var("ADMIN_ACCOUNT_IDS")
+        .map(|ids| {
+            ids.split(',')
+                .filter_map(|id| id.trim().parse::<i32>().ok())
+                .any(|id| id == account_id.0)
+        })
+        .unwrap_or(false)
+}
+
 pub fn auth() -> impl Filter<Extract = (Session,), Error = warp::Rejection> + Clone {
-    warp::header::<String>("Authorization").and_then(|token: String| {
-        let token = match verify_token(token) {
-            Ok(t) => t,
-            Err(_) => return future::ready(Err(warp::reject::reject())),
-        };
+    warp::header::<String>("Authorization").and_then(|header: String| async move {
+        match session_from_header(header).await {
+            Some(session) => Ok(session),
+            None => Err(warp::reject::reject()),
+        }
+    })
+}
+
+/// X-API-Key 헤더의 API 키로 세션을 만든다. 폐기된 키나 형식이 잘못된 키는 거부한다.
+/// API 키로 만든 세션에는 만료 시각(exp)이 없다.
+pub fn api_key_auth(
+    store: Store,
+) -> impl Filter<Extract = (Session,), Error = warp::Rejection> + Clone {
+    warp::header::<String>("X-API-Key").and_then(move |key: String| {
+        let store = store.clone();
+        async move {
+            match session_from_api_key(store, key).await {
+                Some(session) => Ok(session),
+                None => Err(warp::reject::reject()),
+            }
+        }
+    })
+}
+
+/// Authorization 헤더의 토큰이나 X-API-Key 헤더의 API 키 중 하나로 인증한다.
+/// 계정 설정(/me/...)을 바꾸는 경로는 키가 유출되어도 계정을 빼앗기지 않도록 auth()만 사용한다.
+pub fn auth_or_api_key(
+    store: Store,
+) -> impl Filter<Extract = (Session,), Error = warp::Rejection> + Clone {
+    auth().or(api_key_auth(store)).unify()
+}
+
+/// auth_or_api_key()와 같지만 REQUIRE_VERIFIED_EMAIL=true이면 이메일을 인증한 계정만 통과시킨다.
+/// 질문과 답변을 쓰거나 바꾸는 경로에 사용한다.
+pub fn write_auth(
+    store: Store,
+) -> impl Filter<Extract = (Session,), Error = warp::Rejection> + Clone {
+    auth_or_api_key(store.clone()).and_then(move |session: Session| {
+        let store = store.clone();
+        async move {
+            if env::var("REQUIRE_VERIFIED_EMAIL").as_deref() != Ok("true") {
+                return Ok(session);
+            }
 
-        future::ready(Ok(token))
+            match store.is_email_verified(&session.account_id).await {
+                Ok(true) => Ok(session),
+                Ok(false) => Err(warp::reject::custom(handle_errors::Error::EmailNotVerified)),
+                Err(e) => Err(warp::reject::custom(e)),
+            }
+        }
     })
 }
+
+/// auth()와 같지만 Authorization 헤더가 없거나 토큰이 유효하지 않아도 거부하지 않고 None을 넘긴다.
+/// 로그인 여부에 따라 응답만 달라지는 경로에 사용한다.
+pub fn optional_auth() -> impl Filter<Extract = (Option<Session>,), Error = warp::Rejection> + Clone
+{
+    warp::header::optional::<String>("Authorization").and_then(
+        |header: Option<String>| async move {
+            match header {
+                Some(header) => Ok::<_, warp::Rejection>(session_from_header(header).await),
+                None => Ok(None),
+            }
+        },
+    )
+}
+
+async fn session_from_header(header: String) -> Option<Session> {
+    // 로그인 응답의 token_type에 맞춰 "Bearer <토큰>" 형식을 받고, 예전처럼 토큰만 보내도 허용한다.
+    let token = match header.strip_prefix("Bearer ") {
+        Some(token) => token.trim().to_string(),
+        None => header,
+    };
+
+    // 토큰 복호화는 CPU를 쓰는 작업이므로 비동기 실행기의 스레드를 막지 않도록 블로킹 스레드 풀에서 실행한다.
+    match tokio::task::spawn_blocking(move || verify_token(token)).await {
+        Ok(Ok(session)) => {
+            record_account_id(&session);
+            Some(session)
+        }
+        _ => None,
+    }
+}
+
+// 요청 범위(span)에 인증된 계정의 id를 남겨 계정별로 로그를 찾을 수 있게 한다.
+// 범위에 account_id 필드가 선언되어 있어야 기록되며, 익명 요청에는 남기지 않는다.
+fn record_account_id(session: &Session) {
+    tracing::Span::current().record("account_id", &session.account_id.0);
+}
+
+async fn session_from_api_key(store: Store, key: String) -> Option<Session> {
+    let (id, secret) = key.split_once('.')?;
+    let id = id.parse::<i32>().ok()?;
+    let secret = secret.to_string();
+
+    let (account_id, key_hash) = store.get_api_key_hash(id).await.ok()??;
+
+    // 비밀번호 검증과 마찬가지로 해시 검증도 블로킹 스레드 풀에서 실행한다.
+    match tokio::task::spawn_blocking(move || verify_password(&key_hash, secret.as_bytes())).await {
+        Ok(Ok(true)) => {
+            let session = Session {
+                exp: None,
+                account_id,
+                nbf: None,
+            };
+            record_account_id(&session);
+            Some(session)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,22 @@
+use serde::Serialize;
+
+/// main.rs에 등록된 경로 하나의 정보
+#[derive(Serialize, Debug, Clone)]
+pub struct RouteInfo {
+    pub name: &'static str,
+    pub method: &'static str,
+    pub path: &'static str,
+}
+
+impl RouteInfo {
+    pub const fn new(name: &'static str, method: &'static str, path: &'static str) -> Self {
+        RouteInfo { name, method, path }
+    }
+}
+
+// warp 필터는 합성된 뒤에는 경로 정보를 꺼낼 수 없으므로 main.rs가 관리하는 목록을 그대로 돌려준다.
+pub async fn list_routes(
+    routes: &'static [RouteInfo],
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&routes))
+}
@@ -0,0 +1,35 @@
+use chrono::{TimeZone, Utc};
+use warp::http::StatusCode;
+
+use crate::store::Store;
+
+// 서버가 살아 있는지만 확인하므로 데이터베이스에 접근하지 않는다.
+pub async fn health() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::with_status("OK", StatusCode::OK))
+}
+
+// 배포 확인용으로 지금 실행 중인 빌드의 버전, 커밋 해시, 빌드 시각을 반환한다.
+pub async fn version() -> Result<impl warp::Reply, warp::Rejection> {
+    let build_timestamp = env!("RUST_WEB_DEV_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+        .map(|time| time.to_rfc3339());
+
+    Ok(warp::reply::json(&serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "gitSha": env!("RUST_WEB_DEV_GIT_SHA"),
+        "buildTimestamp": build_timestamp,
+    })))
+}
+
+// 배포 후 마이그레이션이 적용되었는지 확인할 수 있도록 현재 마이그레이션 버전을 함께 반환한다.
+pub async fn health_detailed(store: Store) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.current_migration_version().await {
+        Ok(version) => Ok(warp::reply::json(&serde_json::json!({
+            "status": "ok",
+            "migrationVersion": version,
+        }))),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
@@ -1,3 +1,11 @@
+pub mod account;
+pub mod admin;
 pub mod answer;
+#[cfg(test)]
+mod auth_flow;
 pub mod authentication;
+pub mod debug;
+pub mod health;
+pub mod moderation;
 pub mod question;
+pub mod stats;
@@ -0,0 +1,20 @@
+use crate::profanity::{ApiLayerClient, check_profanity_details};
+use crate::routes::authentication::is_admin;
+use crate::types::account::Session;
+use crate::types::moderation::ModerationRequest;
+
+// 저장하지 않고 내용을 검사해 어떤 금칙어가 걸렸는지 보여준다. 관리자만 사용할 수 있다.
+pub async fn check(
+    session: Session,
+    client: ApiLayerClient,
+    request: ModerationRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !is_admin(&session.account_id) {
+        return Err(warp::reject::custom(handle_errors::Error::Unauthorized));
+    }
+
+    match check_profanity_details(&client, request.content).await {
+        Ok(report) => Ok(warp::reply::json(&report)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
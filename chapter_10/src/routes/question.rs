@@ -1,13 +1,24 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 use tracing::{Level, event, info, instrument};
-use warp::http::StatusCode;
+use warp::Reply;
+use warp::http::{HeaderValue, StatusCode};
 
-use crate::profanity::check_profanity; // 새로 만든 파일에서 내보낸 check_profanity 함수를 임포트한다.
+use crate::filters::PRETTY_PARAM;
+use crate::profanity::ProfanityChecker; // 새로 만든 파일에서 내보낸 금칙어 검사기를 임포트한다.
+use crate::routes::authentication::{confirm_password, is_admin};
+use crate::sanitize::sanitize_html;
 use crate::store::Store;
-use crate::types::account::Session; // account 모듈에서 Session 타입을 임포트한다.
-use crate::types::pagination::{Pagination, extract_pagination};
-use crate::types::question::{NewQuestion, Question};
+use crate::types::account::{PasswordConfirmation, Session}; // account 모듈에서 Session 타입을 임포트한다.
+use crate::types::pagination::{
+    CursorPage, Pagination, extract_pagination, extract_range, reject_unknown_params,
+};
+use crate::types::question::{
+    NewQuestion, NewQuestions, Question, QuestionExport, QuestionStatus, QuestionStatusChange,
+    QuestionWithAuthor, TagChange, parse_fields, validate_lengths,
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct APIResponse {
@@ -32,54 +43,305 @@ struct BadWordsResponse {
     censored_content: String,
 }
 
+/// 질문 목록을 반환한다.
+/// 결과가 없어도 에러가 아니며 기본적으로 200과 빈 배열([])을 반환한다.
+/// EMPTY_LIST_NO_CONTENT=true이면 offset 방식의 빈 목록에 본문 없이 204를 반환한다.
+/// 커서 방식은 next_cursor를 함께 돌려줘야 하므로 항상 200이다.
+/// ?status=open처럼 상태로 거를 수 있고, 페이지 매기기 매개변수와 함께 쓸 수 있다.
+/// ?fields=id,title처럼 필요한 필드만 골라 받을 수 있다(offset 방식에서만 지원한다).
+/// ?unanswered=true이면 답변이 없는 질문만 반환한다(offset 방식에서만 지원하며 fields와 함께 쓸 수 없다).
+/// 로그인하지 않아도 조회할 수 있으며, 로그인한 경우에는 session이 함께 넘어온다.
 #[instrument]
 pub async fn get_questions(
-    params: HashMap<String, String>,
+    mut params: HashMap<String, String>,
+    range: Option<String>,
+    session: Option<Session>,
     store: Store,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    event!(target: "practical_rust_book", Level::INFO, "querying questions");
+    event!(
+        target: crate::LOG_TARGET,
+        Level::INFO,
+        account_id = ?session.as_ref().map(|session| session.account_id.0),
+        "querying questions"
+    );
     let mut pagination = Pagination::default(); // 기본 매개변수 Pagination 값을 가지는 가변 변수를 만든다.
+    let mut partial = false;
+
+    reject_unknown_params(
+        &params,
+        &["limit", "offset", "after", "status", "fields", "unanswered"],
+    )?;
+    params.remove(PRETTY_PARAM);
+
+    // status, fields, unanswered는 페이지 매기기 매개변수가 아니므로 먼저 꺼낸다.
+    let status = params
+        .remove("status")
+        .map(|status| status.parse::<QuestionStatus>())
+        .transpose()?;
+    let fields = params
+        .remove("fields")
+        .map(|fields| parse_fields(&fields))
+        .transpose()?;
+    // ?unanswered=true이면 답변이 하나도 없는 질문만 반환한다.
+    let unanswered = params.remove("unanswered").as_deref() == Some("true");
 
     if !params.is_empty() {
         event!(Level::INFO, pagination = true);
-        let pagination = extract_pagination(params)?; // 페이지 매기기 객체(pagination object)가 비어있지 않은 경우, 위 가변 변수의 값을 클라이언트가 전달한 Pagination 값으로 대체한다.
+        pagination = extract_pagination(params)?; // 페이지 매기기 객체(pagination object)가 비어있지 않은 경우, 위 가변 변수의 값을 클라이언트가 전달한 Pagination 값으로 대체한다.
+    } else if let Some(range) = range {
+        // 쿼리 매개변수가 없을 때만 Range 헤더를 본다.
+        event!(Level::INFO, range = %range);
+        pagination = extract_range(&range)?;
+        partial = true;
     }
     info!(pagination = false);
-    match store
-        .get_questions(pagination.limit, pagination.offset)
-        .await
-    {
-        Ok(res) => Ok(warp::reply::json(&res)),
-        Err(e) => Err(warp::reject::custom(e)), // 에러의 경우, handle-errors 크레이트에서 정의한 에러 값을 에러 핸들러에 넘긴다.
+
+    let clamped_to = pagination.apply_page_size()?;
+
+    if unanswered && (fields.is_some() || pagination.after.is_some()) {
+        return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+            "unanswered cannot be combined with fields or after".to_string(),
+        )));
+    }
+
+    let mut response = if let Some(fields) = fields {
+        if pagination.after.is_some() {
+            return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+                "fields cannot be combined with after".to_string(),
+            )));
+        }
+
+        let questions = store
+            .get_questions_fields(&fields, pagination.limit, pagination.offset, status)
+            .await?;
+        warp::reply::json(&questions).into_response()
+    } else if let Some(after) = pagination.after {
+        // 커서 모드에서는 다음 페이지를 요청할 커서를 함께 돌려준다.
+        let questions = store
+            .get_questions_after(after, pagination.limit, status)
+            .await?;
+        let next_cursor = match pagination.limit {
+            Some(limit) if questions.len() == limit as usize => questions.last().map(|q| q.id.0),
+            _ => None, // 요청한 개수보다 적게 왔다면 마지막 페이지이다.
+        };
+
+        warp::reply::json(&CursorPage {
+            items: questions,
+            next_cursor,
+        })
+        .into_response()
+    } else {
+        let questions = if unanswered {
+            store
+                .get_unanswered_questions(pagination.limit, pagination.offset, status)
+                .await
+        } else {
+            store
+                .get_questions(pagination.limit, pagination.offset, status)
+                .await
+        };
+
+        match questions {
+            Ok(res) if partial => {
+                if res.is_empty() {
+                    return Err(warp::reject::custom(
+                        handle_errors::Error::RangeNotSatisfiable,
+                    ));
+                }
+
+                // 206과 함께 실제로 반환한 범위를 알려준다. 전체 개수는 세지 않으므로 *로 둔다.
+                let end = pagination.offset as u64 + res.len() as u64 - 1;
+                let content_range = format!("items {}-{}/*", pagination.offset, end);
+                let mut response =
+                    warp::reply::with_status(warp::reply::json(&res), StatusCode::PARTIAL_CONTENT)
+                        .into_response();
+                if let Ok(content_range) = HeaderValue::from_str(&content_range) {
+                    response
+                        .headers_mut()
+                        .insert("Content-Range", content_range);
+                }
+                response
+            }
+            Ok(res) => {
+                let no_content = std::env::var("EMPTY_LIST_NO_CONTENT").as_deref() == Ok("true");
+                let mut response = list_response(&res, no_content);
+
+                // 요청한 개수만큼 왔다면 다음 페이지가 있을 수 있다.
+                let has_next = pagination.limit == Some(res.len() as u32);
+                let link = pagination
+                    .link_header("/questions", has_next)
+                    .filter(|_| response.status() != StatusCode::NO_CONTENT);
+                if let Some(link) = link.and_then(|link| HeaderValue::from_str(&link).ok()) {
+                    response.headers_mut().insert("Link", link);
+                }
+                response
+            }
+            Err(e) => return Err(warp::reject::custom(e)), // 에러의 경우, handle-errors 크레이트에서 정의한 에러 값을 에러 핸들러에 넘긴다.
+        }
+    };
+
+    if let Some(max_page_size) = clamped_to {
+        // 요청한 limit이 최대값보다 커서 줄였다는 사실을 헤더로 알린다.
+        response
+            .headers_mut()
+            .insert("X-Page-Size-Clamped", HeaderValue::from(max_page_size));
+    }
+
+    Ok(response)
+}
+
+// 목록 응답을 만든다. 빈 목록이면 기본적으로 200과 []를, no_content가 true이면 본문 없이 204를 반환한다.
+fn list_response<T: Serialize>(items: &[T], no_content: bool) -> warp::reply::Response {
+    if items.is_empty() && no_content {
+        return StatusCode::NO_CONTENT.into_response();
     }
+    warp::reply::json(&items).into_response()
 }
 
+/// ?validate_only=true이면 길이와 금칙어 검사만 하고 저장하지 않으며, 금칙어를 가린 질문을 200으로 반환한다.
+/// 제목이 매우 비슷한 질문이 이미 있으면 409로 기존 질문의 id를 알려주며, ?allow_duplicates=true이면 검사하지 않는다.
+/// 유사도 기준(similarity_percent)은 서버를 띄울 때 DUPLICATE_TITLE_SIMILARITY_PERCENT(0~100, 기본 80)로 정한다.
+/// 본문이 배열이면 POST /questions/batch와 같이 처리하며, 이때는 위의 쿼리 매개변수를 쓸 수 없다.
 pub async fn add_question(
     session: Session,
     store: Store,
-    new_question: NewQuestion,
+    profanity: ProfanityChecker,
+    similarity_percent: u32,
+    params: HashMap<String, String>,
+    new_questions: NewQuestions,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    reject_unknown_params(&params, &["validate_only", "allow_duplicates"])?;
+    let validate_only = params.get("validate_only").map(String::as_str) == Some("true");
+    let allow_duplicates = params.get("allow_duplicates").map(String::as_str) == Some("true");
+
+    let new_question = match new_questions {
+        NewQuestions::One(new_question) => new_question,
+        NewQuestions::Many(new_questions) => {
+            // 검사만 하려던 요청이 저장되지 않도록 배열에는 쿼리 매개변수를 허용하지 않는다.
+            if params.keys().any(|key| key != PRETTY_PARAM) {
+                return Err(warp::reject::custom(handle_errors::Error::InvalidInput(
+                    "Query parameters are not supported for arrays".to_string(),
+                )));
+            }
+            return add_questions(session, store, profanity, new_questions)
+                .await
+                .map(Reply::into_response);
+        }
+    };
+
     let account_id = session.account_id;
-    let title = match check_profanity(new_question.title).await {
+    validate_lengths(&new_question.title, &new_question.content)?;
+
+    // 금칙어 검사 전후를 비교해 내용이 바뀌었는지 클라이언트에 알려주기 위해 원문을 남겨둔다.
+    let original_title = new_question.title.clone();
+    let original_content = new_question.content.clone();
+
+    let title = match profanity.check(new_question.title).await {
         // 함수를 호출하고 퓨처를 기다린 후 Result에 일치시킨다.
         Ok(res) => res,
         Err(e) => return Err(warp::reject::custom(e)),
     };
 
-    let content = match check_profanity(new_question.content).await {
+    let content = match profanity.check(new_question.content).await {
         // 이 작업을 두 번째로 한다. 첫 번째는 title이었다. 이제 질문 자체 안에 있는 금칙어를 검사한다.
         Ok(res) => res,
         Err(e) => return Err(warp::reject::custom(e)),
     };
 
+    let censored = title != original_title || content != original_content;
+
+    if !allow_duplicates {
+        // 저장되는 제목과 비교하도록 금칙어를 가린 제목으로 찾는다.
+        if let Some(existing) = store
+            .find_similar_question(&title, similarity_percent as f32 / 100.0)
+            .await?
+        {
+            return Err(warp::reject::custom(
+                handle_errors::Error::DuplicateQuestion(existing.0),
+            ));
+        }
+    }
+
     let question = NewQuestion {
         title: title,
-        content,
+        content: sanitize_html(content), // 금칙어 검사를 마친 내용에만 적용한다.
         tags: new_question.tags,
     };
 
+    if validate_only {
+        // 모든 검사를 통과했으므로 저장될 내용을 그대로 보여준다.
+        return Ok(warp::reply::json(&serde_json::json!({
+            "question": question,
+            "censored": censored,
+        }))
+        .into_response());
+    }
+
     match store.add_question(question, account_id).await {
-        Ok(question) => Ok(warp::reply::json(&question)), // 여기까지 왔다면 단순한 문자열과 HTTP 코드 대신에 정확한 질문을 반환한다.
+        // 여기까지 왔다면 단순한 문자열과 HTTP 코드 대신에 정확한 질문을 반환한다.
+        Ok(question) => Ok(warp::reply::json(&serde_json::json!({
+            "question": question,
+            "censored": censored,
+        }))
+        .into_response()),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+// 일괄 추가 시 동시에 실행할 금칙어 검사 작업의 최대 개수
+const PROFANITY_CHECK_CONCURRENCY: usize = 5;
+
+pub async fn add_questions(
+    session: Session,
+    store: Store,
+    profanity: ProfanityChecker,
+    new_questions: Vec<NewQuestion>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = session.account_id;
+    for new_question in &new_questions {
+        validate_lengths(&new_question.title, &new_question.content)?;
+    }
+
+    let semaphore = Arc::new(Semaphore::new(PROFANITY_CHECK_CONCURRENCY)); // 외부 API를 한꺼번에 호출하지 않도록 동시 실행 수를 제한한다.
+
+    let handles: Vec<_> = new_questions
+        .into_iter()
+        .map(|new_question| {
+            let semaphore = semaphore.clone();
+            let profanity = profanity.clone();
+            tokio::spawn(async move {
+                let _permit = semaphore
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| handle_errors::Error::TaskFailed(e.to_string()))?; // 작업이 끝나면 permit이 드롭되어 다음 작업이 실행된다.
+                let (title, content) = tokio::join!(
+                    profanity.check(new_question.title),
+                    profanity.check(new_question.content)
+                );
+
+                Ok::<NewQuestion, handle_errors::Error>(NewQuestion {
+                    title: title?,
+                    content: sanitize_html(content?),
+                    tags: new_question.tags,
+                })
+            })
+        })
+        .collect();
+
+    let mut questions = Vec::with_capacity(handles.len());
+    for handle in handles {
+        // 작업이 패닉하거나 취소되어도 서버를 멈추지 않고 500으로 응답한다.
+        let result = handle
+            .await
+            .map_err(|e| warp::reject::custom(handle_errors::Error::TaskFailed(e.to_string())))?;
+        match result {
+            Ok(question) => questions.push(question),
+            Err(e) => return Err(warp::reject::custom(e)), // 하나라도 실패하면 아무것도 저장하지 않는다.
+        }
+    }
+
+    match store.add_questions(questions, account_id).await {
+        Ok(ids) => Ok(warp::reply::json(&ids)),
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
@@ -143,21 +405,28 @@ pub async fn update_question(
     id: i32,
     session: Session, // auth 미들웨어에서 추출하므로 두 번째 매개변수로 Session xkdlqdmf rleogksek.
     store: Store,
+    profanity: ProfanityChecker,
     question: Question,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let account_id = session.account_id; // account_id를 Session 객체에서 추출하여 다음 함수에 참조로 전달할 수 있도록 한다.
+    validate_lengths(&question.title, &question.content)?;
+
     if store.is_question_owner(id, &account_id).await? {
         // 새로 만든 저장 함수로 해당 질문이 현재 계정으로 생성된 것인지 확인한다.
-        let title = check_profanity(question.title);
-        let content = check_profanity(question.content);
+        // 외부 API를 부르기 전에 거르기 위한 확인이고, 실제 소유자 확인은 store.update_question이 수정과 같은 트랜잭션에서 다시 한다.
+        let title = profanity.check(question.title);
+        let content = profanity.check(question.content);
         let (title, content) = tokio::join!(title, content); // spawn 대신 함수 호출을 개별적으로 래핑할 필요가 없다. join! 매크로 안에서 await 없이 이들을 호출하기만 하면 된다.
 
         if title.is_ok() && content.is_ok() {
             let question = Question {
                 id: question.id,
                 title: title.unwrap(),
-                content: content.unwrap(),
+                content: sanitize_html(content.unwrap()),
                 tags: question.tags,
+                account_id: question.account_id,
+                answer_count: question.answer_count,
+                status: question.status,
             };
 
             match store.update_question(question, id, account_id).await {
@@ -170,26 +439,387 @@ pub async fn update_question(
                 title.expect_err("Expected API call to have failed here"),
             ))
         }
-    } else {
+    } else if store.question_exists(id).await? {
         Err(warp::reject::custom(handle_errors::Error::Unauthorized)) // Session 의 account_id가 데이터베이스의 것과 일치하지 않으면 401 권한 없음 에러를 반환한다.
+    } else {
+        // 질문이 아예 없으면 권한 문제가 아니므로 404를 반환한다.
+        Err(warp::reject::custom(handle_errors::Error::QuestionNotFound))
+    }
+}
+
+/// 태그 배열 전체를 보내지 않고 태그 하나만 더한다.
+pub async fn add_question_tag(
+    id: i32,
+    session: Session,
+    store: Store,
+    change: TagChange,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let tag = validate_tag(&change.tag)?;
+
+    match store.add_question_tag(id, tag, session.account_id).await {
+        Ok(question) => Ok(warp::reply::json(&question)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// 태그 하나를 뺀다. 경로의 태그는 퍼센트 인코딩을 풀어서 사용한다(/questions/1/tags/rust%20web).
+pub async fn remove_question_tag(
+    id: i32,
+    tag: String,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let tag = urlencoding::decode(&tag)
+        .map_err(|_| handle_errors::Error::InvalidInput("Invalid tag".to_string()))?;
+    let tag = validate_tag(&tag)?;
+
+    match store.remove_question_tag(id, tag, session.account_id).await {
+        Ok(question) => Ok(warp::reply::json(&question)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+// 앞뒤 공백을 떼고 빈 태그는 거부한다.
+fn validate_tag(tag: &str) -> Result<String, handle_errors::Error> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(handle_errors::Error::InvalidInput(
+            "Tag must not be empty".to_string(),
+        ));
     }
+    Ok(tag.to_string())
 }
 
 pub async fn delete_question(
     id: i32,
     session: Session,
     store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // 소유자 확인은 store.delete_question이 삭제와 같은 트랜잭션에서 한다.
+    match store.delete_question(id, session.account_id).await {
+        Ok(true) => Ok(warp::reply::with_status(
+            format!("Question {} deleted", id),
+            StatusCode::OK,
+        )),
+        Ok(false) if store.question_exists(id).await? => {
+            Err(warp::reject::custom(handle_errors::Error::Unauthorized))
+        }
+        Ok(false) => Err(warp::reject::custom(handle_errors::Error::QuestionNotFound)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// 제목과 내용에서 q로 전문 검색(full-text search)을 해서 관련도 순으로 반환한다.
+/// ?highlight=true이면 결과마다 일치한 부분을 표시한 발췌문(highlight)을 함께 반환한다.
+/// 예: /questions/search?q=rust+warp&highlight=true&limit=10&offset=0
+pub async fn search_questions(
+    mut params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    reject_unknown_params(&params, &["q", "highlight", "limit", "offset"])?;
+    params.remove(PRETTY_PARAM);
+
+    let query = params
+        .remove("q")
+        .map(|q| q.trim().to_string())
+        .filter(|q| !q.is_empty())
+        .ok_or_else(|| handle_errors::Error::MissingParameter("q".to_string()))?;
+    let highlight = params.remove("highlight").as_deref() == Some("true");
+
+    let mut pagination = Pagination::default();
+    if !params.is_empty() {
+        pagination = extract_pagination(params)?;
+    }
+    pagination.apply_page_size()?;
+
+    match store
+        .search_questions(&query, highlight, pagination.limit, pagination.offset)
+        .await
+    {
+        Ok(results) => Ok(warp::reply::json(&results)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// include=author이면 작성자 정보를 함께 반환한다.
+pub async fn get_question(
+    id: i32,
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    reject_unknown_params(&params, &["include"])?;
+
+    if params.get("include").map(String::as_str) == Some("author") {
+        let mut question = store
+            .get_question_with_author(id)
+            .await?
+            .ok_or(handle_errors::Error::QuestionNotFound)?;
+        hide_author_email(&mut question);
+
+        return Ok(warp::reply::json(&question).into_response());
+    }
+
+    match store.get_question(id).await? {
+        Some(question) => Ok(warp::reply::json(&question).into_response()),
+        None => Err(warp::reject::custom(handle_errors::Error::QuestionNotFound)),
+    }
+}
+
+/// 질문 한 건과 작성자, 모든 답변을 하나의 JSON 문서로 내보낸다.
+/// 브라우저에서 열면 question-<id>.json 파일로 내려받는다.
+pub async fn export_question(id: i32, store: Store) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut question = store
+        .get_question_with_author(id)
+        .await?
+        .ok_or(handle_errors::Error::QuestionNotFound)?;
+    hide_author_email(&mut question);
+
+    let answers = store.get_answers_for_question(id).await?;
+
+    let export = QuestionExport {
+        question,
+        answers,
+        exported_at: chrono::Utc::now(),
+    };
+
+    Ok(warp::reply::with_header(
+        warp::reply::json(&export),
+        "Content-Disposition",
+        format!("attachment; filename=\"question-{}.json\"", id),
+    ))
+}
+
+// 개인 정보 설정으로 이메일을 숨기는 경우(HIDE_AUTHOR_EMAIL=true) 작성자 id만 남긴다.
+fn hide_author_email(question: &mut QuestionWithAuthor) {
+    if std::env::var("HIDE_AUTHOR_EMAIL").as_deref() == Ok("true") {
+        if let Some(author) = question.author.as_mut() {
+            author.email = None;
+        }
+    }
+}
+
+pub async fn get_question_history(
+    id: i32,
+    session: Session,
+    store: Store,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let account_id = session.account_id;
-    if store.is_question_owner(id, &account_id).await? {
-        match store.delete_question(id, account_id).await {
-            Ok(_) => Ok(warp::reply::with_status(
-                format!("Question {} deleted", id),
-                StatusCode::OK,
-            )),
+    if is_admin(&account_id) || store.is_question_owner(id, &account_id).await? {
+        // 질문의 소유자나 관리자만 수정 이력을 볼 수 있다.
+        match store.get_question_history(id).await {
+            Ok(history) => Ok(warp::reply::json(&history)),
+            Err(e) => Err(warp::reject::custom(e)),
+        }
+    } else {
+        Err(warp::reject::custom(handle_errors::Error::Unauthorized))
+    }
+}
+
+pub async fn lock_question(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    set_question_locked(id, session, store, true).await
+}
+
+pub async fn unlock_question(
+    id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    set_question_locked(id, session, store, false).await
+}
+
+async fn set_question_locked(
+    id: i32,
+    session: Session,
+    store: Store,
+    locked: bool,
+) -> Result<warp::reply::Json, warp::Rejection> {
+    let account_id = session.account_id;
+    if is_admin(&account_id) || store.is_question_owner(id, &account_id).await? {
+        // 질문의 소유자나 관리자만 잠그거나 잠금을 풀 수 있다.
+        match store.set_question_locked(id, locked).await {
+            Ok(locked) => Ok(warp::reply::json(&serde_json::json!({
+                "id": id,
+                "locked": locked,
+            }))),
             Err(e) => Err(warp::reject::custom(e)),
         }
     } else {
         Err(warp::reject::custom(handle_errors::Error::Unauthorized))
     }
 }
+
+/// 질문의 상태를 바꾼다. 질문의 소유자나 관리자만 바꿀 수 있다.
+/// 닫힌(closed) 질문을 다시 여는 것처럼 허용되지 않는 변경은 409를 반환한다.
+pub async fn update_question_status(
+    id: i32,
+    session: Session,
+    store: Store,
+    change: QuestionStatusChange,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = session.account_id;
+    let admin = is_admin(&account_id);
+
+    match store
+        .change_question_status(id, change.status, &account_id, admin)
+        .await
+    {
+        Ok(status) => Ok(warp::reply::json(&serde_json::json!({
+            "id": id,
+            "status": status,
+        }))),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// 로그인한 계정의 질문(과 거기에 달린 답변)을 모두 지우고 지운 질문 수를 반환한다.
+/// 되돌릴 수 없으므로 현재 비밀번호를 함께 받아 확인한다.
+pub async fn delete_my_questions(
+    session: Session,
+    store: Store,
+    confirmation: PasswordConfirmation,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    confirm_password(&store, &session.account_id, &confirmation.password).await?;
+
+    match store.delete_questions_by_account(session.account_id).await {
+        Ok(deleted) => Ok(warp::reply::json(&serde_json::json!({
+            "deleted": deleted,
+        }))),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::account::AccountId;
+    use sqlx::postgres::PgPoolOptions;
+    use warp::Filter;
+
+    // 연결을 실제로 맺지 않는 저장소로, 데이터베이스를 쓰지 않는 요청만 처리할 수 있다.
+    fn lazy_store() -> Store {
+        Store {
+            connection: PgPoolOptions::new()
+                .connect_lazy("postgres://localhost:5432/rustwebdev")
+                .unwrap(),
+            read_connection: None,
+        }
+    }
+
+    // main.rs의 add_question 경로에서 인증과 저장소 대신 고정된 값을 넘긴다.
+    fn add_question_route(
+        profanity: ProfanityChecker,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let store = lazy_store();
+        warp::post()
+            .and(warp::path("questions"))
+            .and(warp::path::end())
+            .and(warp::any().map(|| Session {
+                exp: None,
+                account_id: AccountId(1),
+                nbf: None,
+            }))
+            .and(warp::any().map(move || store.clone()))
+            .and(warp::any().map(move || profanity.clone()))
+            .and(warp::any().map(|| 80u32))
+            .and(warp::query())
+            .and(warp::body::json())
+            .and_then(add_question)
+            .recover(handle_errors::return_error)
+    }
+
+    // validate_only와 allow_duplicates를 켜서 데이터베이스를 거치지 않도록 한다.
+    fn validate_request() -> warp::test::RequestBuilder {
+        warp::test::request()
+            .method("POST")
+            .path("/questions?validate_only=true&allow_duplicates=true")
+            .json(&serde_json::json!({
+                "title": "How to use warp?",
+                "content": "I want to write a web server.",
+                "tags": ["rust"],
+            }))
+    }
+
+    #[tokio::test]
+    async fn empty_list_returns_ok_with_empty_array_by_default() {
+        let response = list_response::<Question>(&[], false);
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"[]");
+    }
+
+    #[test]
+    fn empty_list_returns_no_content_when_enabled() {
+        let response = list_response::<Question>(&[], true);
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[test]
+    fn non_empty_list_ignores_no_content() {
+        let question = Question {
+            id: crate::types::question::QuestionId(1),
+            title: "title".to_string(),
+            content: "content".to_string(),
+            tags: None,
+            account_id: None,
+            answer_count: 0,
+            status: QuestionStatus::Open,
+        };
+
+        let response = list_response(&[question], true);
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn add_question_with_passthrough_checker() {
+        let response = validate_request()
+            .reply(&add_question_route(ProfanityChecker::passthrough()))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["question"]["title"], "How to use warp?");
+        assert_eq!(body["censored"], false);
+    }
+
+    #[tokio::test]
+    async fn add_question_reports_censored_content() {
+        let profanity =
+            ProfanityChecker::new(
+                |content: String| async move { Ok(content.replace("warp", "****")) },
+            );
+
+        let response = validate_request()
+            .reply(&add_question_route(profanity))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["question"]["title"], "How to use ****?");
+        assert_eq!(body["censored"], true);
+    }
+
+    #[tokio::test]
+    async fn add_question_maps_checker_failure_to_bad_gateway() {
+        let profanity = ProfanityChecker::new(|_| async {
+            Err(handle_errors::Error::ServerError(
+                handle_errors::APILayerError {
+                    status: 503,
+                    message: "Service Unavailable".to_string(),
+                },
+            ))
+        });
+
+        let response = validate_request()
+            .reply(&add_question_route(profanity))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::store::Store;
+use crate::types::stats::SiteStats;
+
+/// 사이트 통계를 ttl 동안 메모리에 보관하는 캐시
+/// 첫 화면처럼 자주 불리는 경로가 요청마다 테이블 전체를 세지 않도록 한다.
+#[derive(Debug, Clone)]
+pub struct StatsCache {
+    ttl: Duration,
+    cached: Arc<Mutex<Option<(Instant, SiteStats)>>>, // 마지막으로 센 시각과 결과
+}
+
+impl StatsCache {
+    pub fn new(ttl: Duration) -> Self {
+        StatsCache {
+            ttl,
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn get(&self) -> Option<SiteStats> {
+        match &*self.cached.lock().unwrap() {
+            Some((fetched_at, stats)) if fetched_at.elapsed() < self.ttl => Some(stats.clone()),
+            _ => None,
+        }
+    }
+
+    fn set(&self, stats: SiteStats) {
+        *self.cached.lock().unwrap() = Some((Instant::now(), stats));
+    }
+}
+
+// 공개 정보이므로 인증 없이 조회할 수 있다.
+pub async fn get_site_stats(
+    cache: StatsCache,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if let Some(stats) = cache.get() {
+        return Ok(warp::reply::json(&stats));
+    }
+
+    // 락을 잡은 채로 기다리지 않도록 캐시를 확인한 뒤 락을 놓고 데이터베이스에서 센다.
+    match store.get_site_stats().await {
+        Ok(stats) => {
+            cache.set(stats.clone());
+            Ok(warp::reply::json(&stats))
+        }
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
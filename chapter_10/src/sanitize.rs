@@ -0,0 +1,34 @@
+//! 저장하기 전에 질문과 답변 내용의 HTML을 무력화한다.
+//!
+//! 클라이언트가 내용을 HTML로 그대로 그리면 <script> 같은 태그가 실행될 수 있으므로,
+//! SANITIZE_HTML=true일 때 HTML 특수 문자를 엔티티로 바꿔 저장한다.
+//! 태그를 지우지 않고 이스케이프하므로 일반 텍스트는 그대로 남는다.
+//!
+//! 금칙어 검사 뒤에 실행한다. 외부 API가 원문을 검사하도록 하고,
+//! 금칙어를 가린 결과까지 함께 이스케이프하기 위해서다.
+
+use std::env;
+
+/// SANITIZE_HTML=true이면 content의 HTML 특수 문자를 이스케이프하고, 아니면 그대로 돌려준다.
+pub fn sanitize_html(content: String) -> String {
+    if env::var("SANITIZE_HTML").as_deref() != Ok("true") {
+        return content;
+    }
+
+    escape_html(&content)
+}
+
+fn escape_html(content: &str) -> String {
+    let mut escaped = String::with_capacity(content.len());
+    for c in content.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"), // 다른 엔티티와 섞이지 않도록 &도 바꾼다.
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
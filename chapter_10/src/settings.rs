@@ -0,0 +1,158 @@
+use std::any::type_name;
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// 숫자로 된 환경 변수를 서버를 띄울 때 한 번만 읽어 둔 설정
+/// 각 값은 실제로 사용하는 타입으로 파싱하므로, 검사를 통과한 값이 나중에 파싱에 실패하는 일이 없다.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub cors_max_age: u64,                       // CORS_MAX_AGE_SECONDS(기본 3600)
+    pub write_rate_limit_per_minute: u32,        // WRITE_RATE_LIMIT_PER_MINUTE(기본 10)
+    pub trace_sample_rate: f64,                  // TRACE_SAMPLE_RATE(0.0~1.0, 기본 1.0)
+    pub max_concurrent_requests: usize,          // MAX_CONCURRENT_REQUESTS(기본 100)
+    pub shutdown_timeout: Duration,              // SHUTDOWN_TIMEOUT_SECONDS(기본 30)
+    pub request_timeout: Duration,               // REQUEST_TIMEOUT_SECONDS(기본 30)
+    pub stats_cache: Duration,                   // STATS_CACHE_SECONDS(기본 30)
+    pub json_max_depth: usize,                   // JSON_MAX_DEPTH(기본 32)
+    pub db_statement_timeout_ms: u64,            // DB_STATEMENT_TIMEOUT_MS(기본 30000, 0이면 끈다)
+    pub max_answers_per_question: i64,           // MAX_ANSWERS_PER_QUESTION(기본 3)
+    pub duplicate_title_similarity_percent: u32, // DUPLICATE_TITLE_SIMILARITY_PERCENT(0~100, 기본 80)
+    pub profanity_max_bad_words: Option<i64>,    // PROFANITY_MAX_BAD_WORDS(없으면 제한하지 않는다)
+    pub profanity_max_retries: u32,              // PROFANITY_MAX_RETRIES(기본 3)
+    pub profanity_retry_base: Duration,          // PROFANITY_RETRY_BASE_MS(기본 1000)
+    pub profanity_timeout: Duration,             // PROFANITY_TIMEOUT_SECONDS(기본 10)
+}
+
+impl Settings {
+    /// 환경 변수를 읽어 설정을 만든다. 잘못된 값은 첫 번째에서 멈추지 않고 모두 모아 반환한다.
+    pub fn from_env() -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+
+        let settings = Settings {
+            cors_max_age: parse_var("CORS_MAX_AGE_SECONDS", 3600, &mut errors),
+            write_rate_limit_per_minute: parse_var("WRITE_RATE_LIMIT_PER_MINUTE", 10, &mut errors),
+            trace_sample_rate: parse_var("TRACE_SAMPLE_RATE", 1.0_f64, &mut errors).clamp(0.0, 1.0),
+            max_concurrent_requests: parse_var("MAX_CONCURRENT_REQUESTS", 100, &mut errors),
+            shutdown_timeout: Duration::from_secs(parse_var(
+                "SHUTDOWN_TIMEOUT_SECONDS",
+                30,
+                &mut errors,
+            )),
+            request_timeout: Duration::from_secs(parse_var(
+                "REQUEST_TIMEOUT_SECONDS",
+                30,
+                &mut errors,
+            )),
+            stats_cache: Duration::from_secs(parse_var("STATS_CACHE_SECONDS", 30, &mut errors)),
+            json_max_depth: parse_var("JSON_MAX_DEPTH", 32, &mut errors),
+            db_statement_timeout_ms: parse_var("DB_STATEMENT_TIMEOUT_MS", 30000, &mut errors),
+            max_answers_per_question: parse_var("MAX_ANSWERS_PER_QUESTION", 3, &mut errors),
+            duplicate_title_similarity_percent: parse_var(
+                "DUPLICATE_TITLE_SIMILARITY_PERCENT",
+                80,
+                &mut errors,
+            ),
+            profanity_max_bad_words: parse_optional_var("PROFANITY_MAX_BAD_WORDS", &mut errors),
+            profanity_max_retries: parse_var("PROFANITY_MAX_RETRIES", 3, &mut errors),
+            profanity_retry_base: Duration::from_millis(parse_var(
+                "PROFANITY_RETRY_BASE_MS",
+                1000,
+                &mut errors,
+            )),
+            profanity_timeout: Duration::from_secs(parse_var(
+                "PROFANITY_TIMEOUT_SECONDS",
+                10,
+                &mut errors,
+            )),
+        };
+
+        // 요청을 처리할 때 읽는 값도 같은 타입으로 미리 확인해 요청마다 422를 내지 않게 한다.
+        check_var::<usize>("QUESTION_TITLE_MAX_LENGTH", &mut errors);
+        check_var::<usize>("QUESTION_CONTENT_MAX_LENGTH", &mut errors);
+        check_var::<usize>("ANSWER_CONTENT_MAX_LENGTH", &mut errors);
+        check_var::<u32>("DEFAULT_PAGE_SIZE", &mut errors);
+        check_var::<u32>("MAX_PAGE_SIZE", &mut errors);
+
+        if settings.duplicate_title_similarity_percent > 100 {
+            errors.push(format!(
+                "DUPLICATE_TITLE_SIMILARITY_PERCENT must be between 0 and 100, got {}",
+                settings.duplicate_title_similarity_percent
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+// 값이 없으면 기본값을, 파싱할 수 없으면 에러를 기록하고 기본값을 돌려준다.
+fn parse_var<T: FromStr>(name: &str, default: T, errors: &mut Vec<String>) -> T {
+    parse_optional_var(name, errors).unwrap_or(default)
+}
+
+fn parse_optional_var<T: FromStr>(name: &str, errors: &mut Vec<String>) -> Option<T> {
+    let value = env::var(name).ok()?;
+    parse_value(name, &value, errors)
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str, errors: &mut Vec<String>) -> Option<T> {
+    match value.parse::<T>() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            errors.push(format!(
+                "{} must be a valid {}, got {:?}",
+                name,
+                type_name::<T>(),
+                value
+            ));
+            None
+        }
+    }
+}
+
+fn check_var<T: FromStr>(name: &str, errors: &mut Vec<String>) {
+    parse_optional_var::<T>(name, errors);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_value_rejects_fractions_and_negatives_for_unsigned_integers() {
+        let mut errors = Vec::new();
+        assert_eq!(
+            parse_value::<u64>("SHUTDOWN_TIMEOUT_SECONDS", "1.5", &mut errors),
+            None
+        );
+        assert_eq!(
+            parse_value::<usize>("JSON_MAX_DEPTH", "-1", &mut errors),
+            None
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "SHUTDOWN_TIMEOUT_SECONDS must be a valid u64, got \"1.5\"".to_string(),
+                "JSON_MAX_DEPTH must be a valid usize, got \"-1\"".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_value_accepts_values_of_the_consumer_type() {
+        let mut errors = Vec::new();
+        assert_eq!(
+            parse_value::<u64>("CORS_MAX_AGE_SECONDS", "600", &mut errors),
+            Some(600)
+        );
+        assert_eq!(
+            parse_value::<f64>("TRACE_SAMPLE_RATE", "0.1", &mut errors),
+            Some(0.1)
+        );
+        assert!(errors.is_empty());
+    }
+}
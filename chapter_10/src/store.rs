@@ -1,56 +1,1159 @@
 // 로컬 JSON 파일을 읽는 부분을 삭제하므로 임포트 세 개는 필요 없다.
-use sqlx::Row;
 use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
+use sqlx::{Executor, Row};
+use std::env;
 
 use crate::types::{
-    account::{Account, AccountId},
-    answer::{Answer, AnswerId, NewAnswer},
-    question::{NewQuestion, Question, QuestionId},
+    account::{Account, AccountId, AccountStats, ApiKey},
+    answer::{Answer, AnswerId, AnswerUpdate, AnswerWithAuthor, NewAnswer},
+    question::{
+        Author, NewQuestion, Question, QuestionHistory, QuestionId, QuestionStatus,
+        QuestionWithAuthor, SearchResult, TagCount, TagRecount,
+    },
+    stats::SiteStats,
 };
 
 use handle_errors::Error;
 
+/// 데이터베이스 연결 문자열을 환경 변수에서 만든다.
+/// DATABASE_URL이 있으면 그대로 사용하고, 없으면 DB_HOST, DB_PORT, DB_USER, DB_PASSWORD, DB_NAME으로
+/// 조합한다. 설정되지 않은 호스트, 포트, 이름은 setup.toml의 값을 사용한다.
+/// 비밀번호에 @, / 같은 문자가 있어도 되도록 사용자 이름과 비밀번호는 URL 인코딩한다.
+pub fn database_url_from_env(default_host: &str, default_port: u16, default_name: &str) -> String {
+    if let Ok(url) = env::var("DATABASE_URL") {
+        return url;
+    }
+
+    let host = env::var("DB_HOST").unwrap_or_else(|_| default_host.to_string());
+    let port = env::var("DB_PORT").unwrap_or_else(|_| default_port.to_string());
+    let name = env::var("DB_NAME").unwrap_or_else(|_| default_name.to_string());
+
+    let credentials = match (env::var("DB_USER"), env::var("DB_PASSWORD")) {
+        (Ok(user), Ok(password)) => format!(
+            "{}:{}@",
+            urlencoding::encode(&user),
+            urlencoding::encode(&password)
+        ),
+        (Ok(user), Err(_)) => format!("{}@", urlencoding::encode(&user)),
+        _ => String::new(),
+    };
+
+    format!("postgres://{}{}:{}/{}", credentials, host, port, name)
+}
+
+/// tags 열을 일관된 형태로 읽는다.
+fn tags_from_row(row: &PgRow) -> Option<Vec<String>> {
+    normalize_tags(row.get("tags"))
+}
+
+/// 열 자체가 NULL이면 None, 빈 배열이면 Some(vec![])을 반환하고
+/// 배열 안의 NULL 항목은 버리며 중복된 태그는 처음 나온 순서대로 하나만 남긴다.
+fn normalize_tags(tags: Option<Vec<Option<String>>>) -> Option<Vec<String>> {
+    tags.map(|tags| {
+        let mut unique_tags: Vec<String> = Vec::new();
+        for tag in tags.into_iter().flatten() {
+            if !unique_tags.contains(&tag) {
+                unique_tags.push(tag);
+            }
+        }
+        unique_tags
+    })
+}
+
+/// status 열을 읽는다. CHECK 제약 조건으로 세 값만 저장되므로 파싱에 실패하면 open으로 본다.
+fn status_from_row(row: &PgRow) -> QuestionStatus {
+    row.get::<String, _>("status")
+        .parse()
+        .unwrap_or(QuestionStatus::Open)
+}
+
+async fn connect(db_url: &str, statement_timeout_ms: u64) -> PgPool {
+    // 느리거나 잠긴 쿼리가 요청을 무한정 붙잡지 않도록 연결마다 statement_timeout을 건다(DB_STATEMENT_TIMEOUT_MS, 0이면 끈다).
+
+    match PgPoolOptions::new()
+        .max_connections(5)
+        .after_connect(move |conn| {
+            Box::pin(async move {
+                conn.execute(format!("SET statement_timeout = {}", statement_timeout_ms).as_str())
+                    .await?;
+                Ok(())
+            })
+        })
+        .connect(db_url)
+        .await
+    {
+        Ok(pool) => pool,
+        Err(e) => panic!("DB 연결을 하지 못했습니다: {}", e), // 데이터베이스에 연결하지 못하는 경우에는 애플리케이션을 종료하도록 한다.
+    }
+}
+
+// 답변을 쓰다가 제약 조건을 어긴 경우를 구체적인 에러로 바꾼다.
+// 23505(유일성 위반)는 409, 23503(외래 키 위반)은 없는 질문이나 부모 답변을 가리킨 것이므로 404로 처리된다.
+// 어느 외래 키를 어겼는지는 메시지 문자열이 아니라 제약 조건 이름으로 구분한다.
+fn answer_error(error: sqlx::Error) -> Error {
+    let (code, constraint) = match error.as_database_error() {
+        Some(database_error) => (
+            database_error.code().map(|code| code.to_string()),
+            database_error
+                .constraint()
+                .map(|constraint| constraint.to_string()),
+        ),
+        None => (None, None),
+    };
+
+    match code.as_deref() {
+        Some("23505") => Error::AnswerConflict,
+        Some("23503") if constraint.as_deref() == Some("answers_parent_answer_id_fkey") => {
+            Error::AnswerNotFound
+        }
+        Some("23503") => Error::QuestionNotFound,
+        _ => Error::DatabaseQueryError(error),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Store {
     pub connection: PgPool, //questions와 answers를 Store의 필드에서 제거하고 연결 풀을 넣는다.
+    /// 읽기 전용 복제본(replica)의 연결 풀로, 없으면 읽기도 connection을 사용한다.
+    pub read_connection: Option<PgPool>,
 }
 
-impl Store {
-    pub async fn new(db_url: &str) -> Self {
-        let db_pool = match PgPoolOptions::new()
-            .max_connections(5)
-            .connect(db_url)
+impl Store {
+    /// read_db_url이 있으면 질문 목록, 질문 조회, 검색 같은 읽기 쿼리는 그 연결로 보낸다.
+    /// 복제본은 조금 늦게 반영될 수 있으므로 쓰기 직후 다시 읽어야 하는 쿼리(소유자 확인 등)는 보내지 않는다.
+    pub async fn new(db_url: &str, read_db_url: Option<&str>, statement_timeout_ms: u64) -> Self {
+        let connection = connect(db_url, statement_timeout_ms).await;
+        let read_connection = match read_db_url {
+            Some(read_db_url) => Some(connect(read_db_url, statement_timeout_ms).await),
+            None => None,
+        };
+
+        Store {
+            connection,
+            read_connection,
+        }
+    }
+
+    // 읽기 전용 연결이 설정되어 있으면 그 연결을, 아니면 기본 연결을 반환한다.
+    fn reader(&self) -> &PgPool {
+        self.read_connection.as_ref().unwrap_or(&self.connection)
+    }
+
+    pub async fn current_migration_version(&self) -> Result<Option<i64>, Error> {
+        // sqlx가 적용한 마이그레이션 중 가장 최근 버전을 읽는다.
+        match sqlx::query(
+            "SELECT version from _sqlx_migrations
+            WHERE success = true
+            ORDER BY version DESC
+            LIMIT 1",
+        )
+        .map(|row: PgRow| row.get("version"))
+        .fetch_optional(&self.connection)
+        .await
+        {
+            Ok(version) => Ok(version),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_questions(
+        &self,
+        limit: Option<u32>,
+        offset: u32,
+        status: Option<QuestionStatus>,
+    ) -> Result<Vec<Question>, Error> {
+        // limit, offset 매개변수를 함수에 전달하여 클라이언트가 페이지 매기기를 원하는지 알려주고 성공했을 때는 질문의 벡터를 반환 받고, 실패했을 때는 에러 타입을 반환 받는다.
+        // status가 None이면 $3이 NULL이 되어 상태와 관계없이 모두 반환한다.
+        match sqlx::query(
+            "SELECT * from questions
+            WHERE ($3::VARCHAR IS NULL OR status = $3)
+            ORDER BY id LIMIT $1 OFFSET $2",
+        ) // 쿼리 함수를 써서 일반 SQL 문을 작성해 넣었고 쿼리에 전달할 변수에 달러 기호($)와 숫자를 추가한다.
+        .bind(limit) // bind 메서드는 SQL 문의 $+숫자 부분을 여기에 지정된 변수로 대체한다.
+        .bind(offset) // 두 번째 bind 항목은 offset 변수이다.
+        .bind(status.map(|status| status.as_str()))
+        .map(|row: PgRow| Question {
+            // 쿼리에서 질문 하나(혹은 전부)를 반환 받고자 하면 map으로 PostgreSQL에서 반환된 row 객체 각각에서 Question을 생성하도록 한다.
+            id: QuestionId(row.get("id")),
+            title: row.get("title"),
+            content: row.get("content"),
+            tags: tags_from_row(&row),
+            account_id: Some(AccountId(row.get("account_id"))),
+            answer_count: row.get("answer_count"),
+            status: status_from_row(&row),
+        })
+        .fetch_all(self.reader()) // fetch_all 메서드는 SQL 문을 실행하고 추가된 질문 모두를 반환한다.
+        .await
+        {
+            Ok(questions) => Ok(questions),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 답변이 하나도 없는 질문만 반환한다. 답변할 질문을 찾는 사용자를 위한 목록이다.
+    pub async fn get_unanswered_questions(
+        &self,
+        limit: Option<u32>,
+        offset: u32,
+        status: Option<QuestionStatus>,
+    ) -> Result<Vec<Question>, Error> {
+        // answer_count 대신 답변 테이블과 조인해, 짝이 되는 답변이 없는 질문만 남긴다.
+        match sqlx::query(
+            "SELECT q.* from questions q
+            LEFT JOIN answers a ON a.corresponding_question = q.id
+            WHERE a.id IS NULL
+            AND ($3::VARCHAR IS NULL OR q.status = $3)
+            ORDER BY q.id LIMIT $1 OFFSET $2",
+        )
+        .bind(limit)
+        .bind(offset)
+        .bind(status.map(|status| status.as_str()))
+        .map(|row: PgRow| Question {
+            id: QuestionId(row.get("id")),
+            title: row.get("title"),
+            content: row.get("content"),
+            tags: tags_from_row(&row),
+            account_id: Some(AccountId(row.get("account_id"))),
+            answer_count: row.get("answer_count"),
+            status: status_from_row(&row),
+        })
+        .fetch_all(self.reader())
+        .await
+        {
+            Ok(questions) => Ok(questions),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_questions_fields(
+        &self,
+        fields: &[(&'static str, &'static str)],
+        limit: Option<u32>,
+        offset: u32,
+        status: Option<QuestionStatus>,
+    ) -> Result<Vec<serde_json::Value>, Error> {
+        // 열 이름은 parse_fields가 허용 목록에서 고른 값만 들어오므로 쿼리 문자열에 넣어도 안전하다.
+        let columns = fields
+            .iter()
+            .map(|(_, column)| *column)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let query = format!(
+            "SELECT {} from questions
+            WHERE ($3::VARCHAR IS NULL OR status = $3)
+            ORDER BY id LIMIT $1 OFFSET $2",
+            columns
+        );
+
+        match sqlx::query(&query)
+            .bind(limit)
+            .bind(offset)
+            .bind(status.map(|status| status.as_str()))
+            .map(|row: PgRow| {
+                let mut question = serde_json::Map::new();
+                for (name, column) in fields {
+                    let value = match *column {
+                        "tags" => serde_json::json!(tags_from_row(&row)),
+                        "title" | "content" | "status" => {
+                            serde_json::json!(row.get::<String, _>(*column))
+                        }
+                        _ => serde_json::json!(row.get::<i32, _>(*column)),
+                    };
+                    question.insert(name.to_string(), value);
+                }
+                serde_json::Value::Object(question)
+            })
+            .fetch_all(self.reader())
+            .await
+        {
+            Ok(questions) => Ok(questions),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_question(&self, question_id: i32) -> Result<Option<Question>, Error> {
+        match sqlx::query("SELECT * from questions WHERE id = $1")
+            .bind(question_id)
+            .map(|row: PgRow| Question {
+                id: QuestionId(row.get("id")),
+                title: row.get("title"),
+                content: row.get("content"),
+                tags: tags_from_row(&row),
+                account_id: Some(AccountId(row.get("account_id"))),
+                answer_count: row.get("answer_count"),
+                status: status_from_row(&row),
+            })
+            .fetch_optional(self.reader())
+            .await
+        {
+            Ok(question) => Ok(question),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_question_with_author(
+        &self,
+        question_id: i32,
+    ) -> Result<Option<QuestionWithAuthor>, Error> {
+        // accounts에서는 id와 email만 가져와 비밀번호가 응답에 섞이지 않게 한다.
+        match sqlx::query(
+            "SELECT q.id, q.title, q.content, q.tags, q.account_id, q.answer_count, q.status,
+                a.id as author_id, a.email as author_email
+            FROM questions q
+            LEFT JOIN accounts a ON a.id = q.account_id
+            WHERE q.id = $1",
+        )
+        .bind(question_id)
+        .map(|row: PgRow| QuestionWithAuthor {
+            question: Question {
+                id: QuestionId(row.get("id")),
+                title: row.get("title"),
+                content: row.get("content"),
+                tags: tags_from_row(&row),
+                account_id: Some(AccountId(row.get("account_id"))),
+                answer_count: row.get("answer_count"),
+                status: status_from_row(&row),
+            },
+            author: row
+                .get::<Option<i32>, _>("author_id")
+                .map(|author_id| Author {
+                    id: AccountId(author_id),
+                    email: row.get("author_email"),
+                }),
+        })
+        .fetch_optional(self.reader())
+        .await
+        {
+            Ok(question) => Ok(question),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_questions_after(
+        &self,
+        after: i32,
+        limit: Option<u32>,
+        status: Option<QuestionStatus>,
+    ) -> Result<Vec<Question>, Error> {
+        // OFFSET 대신 마지막으로 받은 id 이후부터 읽으므로, 그 사이에 행이 추가되어도 중복이나 누락이 생기지 않는다.
+        match sqlx::query(
+            "SELECT * from questions
+            WHERE id > $1 AND ($3::VARCHAR IS NULL OR status = $3)
+            ORDER BY id LIMIT $2",
+        )
+        .bind(after)
+        .bind(limit)
+        .bind(status.map(|status| status.as_str()))
+        .map(|row: PgRow| Question {
+            id: QuestionId(row.get("id")),
+            title: row.get("title"),
+            content: row.get("content"),
+            tags: tags_from_row(&row),
+            account_id: Some(AccountId(row.get("account_id"))),
+            answer_count: row.get("answer_count"),
+            status: status_from_row(&row),
+        })
+        .fetch_all(self.reader())
+        .await
+        {
+            Ok(questions) => Ok(questions),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn search_questions(
+        &self,
+        query: &str,
+        highlight: bool,
+        limit: Option<u32>,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>, Error> {
+        // to_tsquery는 검색어에 공백이나 연산자가 있으면 문법 에러가 나므로, 사용자가 입력한 문장을 그대로 받는 plainto_tsquery를 쓴다.
+        // ts_headline은 비용이 크므로 요청한 경우에만 계산한다.
+        match sqlx::query(
+            "SELECT *,
+                CASE WHEN $2 THEN ts_headline('english', content, plainto_tsquery('english', $1)) END
+                    AS highlight
+            FROM questions
+            WHERE to_tsvector('english', title || ' ' || content) @@ plainto_tsquery('english', $1)
+            ORDER BY ts_rank(to_tsvector('english', title || ' ' || content), plainto_tsquery('english', $1)) DESC, id
+            LIMIT $3 OFFSET $4",
+        )
+        .bind(query)
+        .bind(highlight)
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| SearchResult {
+            question: Question {
+                id: QuestionId(row.get("id")),
+                title: row.get("title"),
+                content: row.get("content"),
+                tags: tags_from_row(&row),
+                account_id: Some(AccountId(row.get("account_id"))),
+                answer_count: row.get("answer_count"),
+                status: status_from_row(&row),
+            },
+            highlight: row.get("highlight"),
+        })
+        .fetch_all(self.reader())
+        .await
+        {
+            Ok(results) => Ok(results),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn add_question(
+        &self,
+        new_question: NewQuestion,
+        account_id: AccountId,
+    ) -> Result<Question, Error> {
+        match sqlx::query(
+            "INSERT INTO questions (title, content, tags, account_id)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, title, content, tags, account_id, answer_count, status",
+        )
+        .bind(new_question.title)
+        .bind(new_question.content)
+        .bind(new_question.tags)
+        .bind(account_id.0)
+        .map(|row: PgRow| Question {
+            id: QuestionId(row.get("id")),
+            title: row.get("title"),
+            content: row.get("content"),
+            tags: tags_from_row(&row),
+            account_id: Some(AccountId(row.get("account_id"))),
+            answer_count: row.get("answer_count"),
+            status: status_from_row(&row),
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(question) => Ok(question),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 제목이 같거나(대소문자 무시) 트라이그램 유사도가 threshold 이상인 질문 중 가장 비슷한 질문의 id를 반환한다.
+    /// 방금 추가된 질문도 찾아야 하므로 복제본이 아닌 connection에서 읽는다.
+    pub async fn find_similar_question(
+        &self,
+        title: &str,
+        threshold: f32,
+    ) -> Result<Option<QuestionId>, Error> {
+        match sqlx::query(
+            "SELECT id FROM questions
+            WHERE lower(title) = lower($1) OR similarity(title, $1) >= $2
+            ORDER BY similarity(title, $1) DESC, id
+            LIMIT 1",
+        )
+        .bind(title)
+        .bind(threshold)
+        .map(|row: PgRow| QuestionId(row.get("id")))
+        .fetch_optional(&self.connection)
+        .await
+        {
+            Ok(question_id) => Ok(question_id),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn add_questions(
+        &self,
+        new_questions: Vec<NewQuestion>,
+        account_id: AccountId,
+    ) -> Result<Vec<QuestionId>, Error> {
+        // 하나의 트랜잭션 안에서 모두 추가하고, 하나라도 실패하면 커밋하지 않는다(트랜잭션이 드롭되면 롤백된다).
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        let mut ids = Vec::with_capacity(new_questions.len());
+        for new_question in new_questions {
+            match sqlx::query(
+                "INSERT INTO questions (title, content, tags, account_id)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id",
+            )
+            .bind(new_question.title)
+            .bind(new_question.content)
+            .bind(new_question.tags)
+            .bind(account_id.0)
+            .map(|row: PgRow| QuestionId(row.get("id")))
+            .fetch_one(&mut tx)
+            .await
+            {
+                Ok(id) => ids.push(id),
+                Err(error) => {
+                    tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                    return Err(Error::DatabaseQueryError(error));
+                }
+            }
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(ids),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn update_question(
+        &self,
+        question: Question,
+        id: i32,
+        account_id: AccountId, // 경로 핸들러에서 전달된 AccountID 매개변수를 함수에 추가한다.
+    ) -> Result<Question, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        // 소유자 확인과 수정을 같은 트랜잭션에서 한다.
+        // FOR UPDATE로 행을 잠가 커밋할 때까지 다른 요청이 소유자를 바꾸지 못하게 한다.
+        match sqlx::query("SELECT account_id from questions WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .map(|row: PgRow| AccountId(row.get("account_id")))
+            .fetch_optional(&mut tx)
+            .await
+        {
+            Ok(Some(owner)) if owner == account_id => {}
+            Ok(Some(_)) => return Err(Error::Unauthorized), // 커밋하지 않은 트랜잭션은 드롭될 때 롤백된다.
+            Ok(None) => return Err(Error::QuestionNotFound), // 확인한 뒤 다른 요청이 질문을 지운 경우
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        }
+
+        // 수정하기 전의 질문을 이력 테이블에 먼저 남긴다.
+        if let Err(error) = sqlx::query(
+            "INSERT INTO question_history (question_id, title, content, tags, account_id)
+            SELECT id, title, content, tags, $2
+            FROM questions
+            WHERE id = $1 and account_id = $2",
+        )
+        .bind(id)
+        .bind(account_id.0)
+        .execute(&mut tx)
+        .await
+        {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return Err(Error::DatabaseQueryError(error));
+        }
+
+        let question = match sqlx::query(
+            // 질문을 수정하려는 계쩡이 해당 질문을 소유하는지 확인하는 WHERE 절을 추가한다.
+            "UPDATE questions
+            SET title = $1, content = $2, tags = $3
+            WHERE id = $4 and account_id = $5 
+            RETURNING id, title, content, tags, account_id, answer_count, status",
+        )
+        .bind(question.title)
+        .bind(question.content)
+        .bind(question.tags)
+        .bind(id)
+        .bind(account_id.0) // AccountId에 .0으로 단일 필드 값을 바인딩한다.
+        .map(|row: PgRow| Question {
+            id: QuestionId(row.get("id")),
+            title: row.get("title"),
+            content: row.get("content"),
+            tags: tags_from_row(&row),
+            account_id: Some(AccountId(row.get("account_id"))),
+            answer_count: row.get("answer_count"),
+            status: status_from_row(&row),
+        })
+        .fetch_one(&mut tx)
+        .await
+        {
+            Ok(question) => question,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(question),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 질문에 태그 하나를 더한다. 이미 있는 태그라면 그대로 둔다.
+    pub async fn add_question_tag(
+        &self,
+        id: i32,
+        tag: String,
+        account_id: AccountId,
+    ) -> Result<Question, Error> {
+        self.change_question_tag(
+            id,
+            tag,
+            account_id,
+            "CASE WHEN $1 = ANY(COALESCE(tags, '{}')) THEN tags ELSE array_append(tags, $1) END",
+        )
+        .await
+    }
+
+    /// 질문에서 태그 하나를 뺀다. 없는 태그라면 그대로 둔다.
+    pub async fn remove_question_tag(
+        &self,
+        id: i32,
+        tag: String,
+        account_id: AccountId,
+    ) -> Result<Question, Error> {
+        self.change_question_tag(id, tag, account_id, "array_remove(tags, $1)")
+            .await
+    }
+
+    // update_question처럼 소유자 확인, 이력 기록, 수정을 한 트랜잭션에서 한다.
+    // new_tags는 $1(태그)로 새 tags 값을 만드는 SQL 식이다.
+    async fn change_question_tag(
+        &self,
+        id: i32,
+        tag: String,
+        account_id: AccountId,
+        new_tags: &'static str,
+    ) -> Result<Question, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        match sqlx::query("SELECT account_id from questions WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .map(|row: PgRow| AccountId(row.get("account_id")))
+            .fetch_optional(&mut tx)
+            .await
+        {
+            Ok(Some(owner)) if owner == account_id => {}
+            Ok(Some(_)) => return Err(Error::Unauthorized),
+            Ok(None) => return Err(Error::QuestionNotFound),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        }
+
+        if let Err(error) = sqlx::query(
+            "INSERT INTO question_history (question_id, title, content, tags, account_id)
+            SELECT id, title, content, tags, $2
+            FROM questions
+            WHERE id = $1",
+        )
+        .bind(id)
+        .bind(account_id.0)
+        .execute(&mut tx)
+        .await
+        {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return Err(Error::DatabaseQueryError(error));
+        }
+
+        let sql = format!(
+            "UPDATE questions SET tags = {}
+            WHERE id = $2
+            RETURNING id, title, content, tags, account_id, answer_count, status",
+            new_tags
+        );
+
+        let question = match sqlx::query(&sql)
+            .bind(tag)
+            .bind(id)
+            .map(|row: PgRow| Question {
+                id: QuestionId(row.get("id")),
+                title: row.get("title"),
+                content: row.get("content"),
+                tags: tags_from_row(&row),
+                account_id: Some(AccountId(row.get("account_id"))),
+                answer_count: row.get("answer_count"),
+                status: status_from_row(&row),
+            })
+            .fetch_one(&mut tx)
+            .await
+        {
+            Ok(question) => question,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(question),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn delete_questions_by_account(&self, account_id: AccountId) -> Result<u64, Error> {
+        // 답변을 먼저 지운 뒤 질문을 지우며, 하나라도 실패하면 아무것도 지워지지 않는다.
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        if let Err(error) = sqlx::query(
+            "DELETE FROM answers
+            WHERE corresponding_question IN (SELECT id FROM questions WHERE account_id = $1)",
+        )
+        .bind(account_id.0)
+        .execute(&mut tx)
+        .await
+        {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return Err(Error::DatabaseQueryError(error));
+        }
+
+        let deleted = match sqlx::query("DELETE FROM questions WHERE account_id = $1")
+            .bind(account_id.0)
+            .execute(&mut tx)
+            .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(deleted),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn recount_tags(&self) -> Result<TagRecount, Error> {
+        // 정리와 집계를 한 트랜잭션에서 해서, 정리한 결과를 기준으로 센 값이 반환되도록 한다.
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        // tags_from_row와 같은 규칙으로, NULL 항목을 버리고 중복된 태그는 처음 나온 순서대로 하나만 남긴다.
+        let fixed_questions = match sqlx::query(
+            "UPDATE questions q
+            SET tags = COALESCE((
+                SELECT array_agg(tag ORDER BY first_position)
+                FROM (
+                    SELECT tag, MIN(position) AS first_position
+                    FROM unnest(q.tags) WITH ORDINALITY AS t(tag, position)
+                    WHERE tag IS NOT NULL
+                    GROUP BY tag
+                ) unique_tags
+            ), '{}')
+            WHERE q.tags IS NOT NULL
+            AND (
+                array_position(q.tags, NULL) IS NOT NULL
+                OR cardinality(q.tags) <> (SELECT COUNT(DISTINCT tag) FROM unnest(q.tags) AS tag)
+            )",
+        )
+        .execute(&mut tx)
+        .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        let tags = match sqlx::query(
+            "SELECT tag, COUNT(*) AS count
+            FROM questions, unnest(tags) AS tag
+            GROUP BY tag
+            ORDER BY count DESC, tag",
+        )
+        .map(|row: PgRow| TagCount {
+            tag: row.get("tag"),
+            count: row.get("count"),
+        })
+        .fetch_all(&mut tx)
+        .await
+        {
+            Ok(tags) => tags,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        match tx.commit().await {
+            Ok(_) => Ok(TagRecount {
+                fixed_questions,
+                tags,
+            }),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_question_history(
+        &self,
+        question_id: i32,
+    ) -> Result<Vec<QuestionHistory>, Error> {
+        match sqlx::query(
+            "SELECT * from question_history
+            WHERE question_id = $1
+            ORDER BY created_on DESC",
+        )
+        .bind(question_id)
+        .map(|row: PgRow| QuestionHistory {
+            id: row.get("id"),
+            question_id: QuestionId(row.get("question_id")),
+            title: row.get("title"),
+            content: row.get("content"),
+            tags: tags_from_row(&row),
+            account_id: AccountId(row.get("account_id")),
+            created_on: row.get("created_on"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(history) => Ok(history),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 질문의 잠금 상태를 바꾼다. 권한(소유자나 관리자) 검사는 호출하는 쪽에서 한다.
+    /// 바뀐 행이 없으면 해당 질문이 없는 것이므로 QuestionNotFound를 반환한다.
+    pub async fn set_question_locked(&self, question_id: i32, locked: bool) -> Result<bool, Error> {
+        match sqlx::query("UPDATE questions SET locked = $1 WHERE id = $2")
+            .bind(locked)
+            .bind(question_id)
+            .execute(&self.connection)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(Error::QuestionNotFound),
+            Ok(_) => Ok(locked),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 질문의 상태를 바꾼다. 질문의 소유자나 관리자(is_admin)만 바꿀 수 있다.
+    /// 현재 상태를 읽고 바꾸는 사이에 다른 요청이 끼어들지 못하도록 한 트랜잭션에서 질문을 잠그고 처리한다.
+    pub async fn change_question_status(
+        &self,
+        question_id: i32,
+        status: QuestionStatus,
+        account_id: &AccountId,
+        is_admin: bool,
+    ) -> Result<QuestionStatus, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        let current =
+            match sqlx::query("SELECT account_id, status FROM questions WHERE id = $1 FOR UPDATE")
+                .bind(question_id)
+                .map(|row: PgRow| (AccountId(row.get("account_id")), status_from_row(&row)))
+                .fetch_optional(&mut tx)
+                .await
+            {
+                Ok(Some((owner, current))) if is_admin || owner == *account_id => current,
+                Ok(Some(_)) => return Err(Error::Unauthorized), // 커밋하지 않은 트랜잭션은 드롭될 때 롤백된다.
+                Ok(None) => return Err(Error::QuestionNotFound),
+                Err(error) => {
+                    tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                    return Err(Error::DatabaseQueryError(error));
+                }
+            };
+
+        if !current.can_transition_to(status, is_admin) {
+            return Err(Error::InvalidStatusTransition(
+                current.as_str().to_string(),
+                status.as_str().to_string(),
+            ));
+        }
+
+        match sqlx::query("UPDATE questions SET status = $1 WHERE id = $2")
+            .bind(status.as_str())
+            .bind(question_id)
+            .execute(&mut tx)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => return Err(Error::QuestionNotFound),
+            Ok(_) => {}
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(status),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn is_question_locked(&self, question_id: &QuestionId) -> Result<bool, Error> {
+        match sqlx::query("SELECT locked from questions where id = $1")
+            .bind(question_id.0)
+            .map(|row: PgRow| row.get("locked"))
+            .fetch_optional(&self.connection)
+            .await
+        {
+            Ok(locked) => Ok(locked.unwrap_or(false)), // 없는 질문은 답변을 추가할 때 외래 키 에러로 처리된다.
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn delete_question(
+        &self,
+        question_id: i32,
+        account_id: AccountId,
+    ) -> Result<bool, Error> {
+        // 질문에 달린 답변과 질문을 함께 지우므로 트랜잭션으로 묶어 일부만 삭제되는 일이 없도록 한다.
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        // 답변을 지우기 전에 같은 트랜잭션 안에서 소유자를 확인하고, 끝날 때까지 다른 요청이 질문을 바꾸지 못하도록 잠근다.
+        match sqlx::query("SELECT 1 FROM questions WHERE id = $1 AND account_id = $2 FOR UPDATE")
+            .bind(question_id)
+            .bind(account_id.0)
+            .fetch_optional(&mut tx)
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => return Ok(false), // 커밋하지 않은 트랜잭션은 드롭될 때 롤백된다.
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        }
+
+        if let Err(error) = sqlx::query("DELETE FROM answers WHERE corresponding_question = $1")
+            .bind(question_id)
+            .execute(&mut tx)
+            .await
+        {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return Err(Error::DatabaseQueryError(error));
+        }
+
+        match sqlx::query("DELETE FROM questions WHERE id = $1 AND account_id = $2")
+            .bind(question_id)
+            .bind(account_id.0)
+            .execute(&mut tx)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 1 => {}
+            Ok(_) => return Err(Error::QuestionNotFound),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        }
+
+        match tx.commit().await {
+            Ok(_) => Ok(true),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn add_answer(
+        &self,
+        new_answer: NewAnswer,
+        account_id: AccountId,
+    ) -> Result<Answer, Error> {
+        match sqlx::query(
+            "INSERT INTO answers (content, corresponding_question, account_id, parent_answer_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, content, corresponding_question, parent_answer_id",
+        )
+        .bind(new_answer.content)
+        .bind(new_answer.question_id.0)
+        .bind(account_id.0)
+        .bind(new_answer.parent_answer_id.map(|id| id.0))
+        .map(|row: PgRow| Answer {
+            id: AnswerId(row.get("id")),
+            content: row.get("content"),
+            question_id: QuestionId(row.get("corresponding_question")),
+            parent_answer_id: row.get::<Option<i32>, _>("parent_answer_id").map(AnswerId),
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(answer) => Ok(answer),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(answer_error(error))
+            }
+        }
+    }
+
+    /// update에 값이 있는 필드만 SET 절에 넣어 답변을 수정한다.
+    /// 답변이 없으면 AnswerNotFound, 다른 계정의 답변이면 Unauthorized를 반환한다.
+    pub async fn update_answer(
+        &self,
+        answer_id: i32,
+        update: AnswerUpdate,
+        account_id: AccountId,
+    ) -> Result<Answer, Error> {
+        let mut tx = match self.connection.begin().await {
+            Ok(tx) => tx,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        };
+
+        // update_question과 마찬가지로 소유자 확인과 수정을 같은 트랜잭션에서 한다.
+        match sqlx::query("SELECT account_id from answers WHERE id = $1 FOR UPDATE")
+            .bind(answer_id)
+            .map(|row: PgRow| AccountId(row.get("account_id")))
+            .fetch_optional(&mut tx)
+            .await
+        {
+            Ok(Some(owner)) if owner == account_id => {}
+            Ok(Some(_)) => return Err(Error::Unauthorized),
+            Ok(None) => return Err(Error::AnswerNotFound),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
+        }
+
+        let mut assignments = Vec::new();
+        if update.content.is_some() {
+            assignments.push(format!("content = ${}", assignments.len() + 1));
+        }
+
+        let sql = format!(
+            "UPDATE answers SET {}
+            WHERE id = ${}
+            RETURNING id, content, corresponding_question, parent_answer_id",
+            assignments.join(", "),
+            assignments.len() + 1
+        );
+
+        let mut query = sqlx::query(&sql);
+        if let Some(content) = update.content {
+            query = query.bind(content);
+        }
+
+        let answer = match query
+            .bind(answer_id)
+            .map(|row: PgRow| Answer {
+                id: AnswerId(row.get("id")),
+                content: row.get("content"),
+                question_id: QuestionId(row.get("corresponding_question")),
+                parent_answer_id: row.get::<Option<i32>, _>("parent_answer_id").map(AnswerId),
+            })
+            .fetch_one(&mut tx)
             .await
         {
-            Ok(pool) => pool,
-            Err(e) => panic!("DB 연결을 하지 못했습니다: {}", e), // 데이터베이스에 연결하지 못하는 경우에는 애플리케이션을 종료하도록 한다.
+            Ok(answer) => answer,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return Err(Error::DatabaseQueryError(error));
+            }
         };
 
-        Store {
-            connection: db_pool,
+        match tx.commit().await {
+            Ok(_) => Ok(answer),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
         }
     }
 
-    pub async fn get_questions(
-        &self,
-        limit: Option<u32>,
-        offset: u32,
-    ) -> Result<Vec<Question>, Error> {
-        // limit, offset 매개변수를 함수에 전달하여 클라이언트가 페이지 매기기를 원하는지 알려주고 성공했을 때는 질문의 벡터를 반환 받고, 실패했을 때는 에러 타입을 반환 받는다.
-        match sqlx::query("SELECT * from questions LIMIT $1 OFFSET $2") // 쿼리 함수를 써서 일반 SQL 문을 작성해 넣었고 쿼리에 전달할 변수에 달러 기호($)와 숫자를 추가한다.
-            .bind(limit) // bind 메서드는 SQL 문의 $+숫자 부분을 여기에 지정된 변수로 대체한다.
-            .bind(offset) // 두 번째 bind 항목은 offset 변수이다.
-            .map(|row: PgRow| Question {
-                // 쿼리에서 질문 하나(혹은 전부)를 반환 받고자 하면 map으로 PostgreSQL에서 반환된 row 객체 각각에서 Question을 생성하도록 한다.
-                id: QuestionId(row.get("id")),
-                title: row.get("title"),
+    pub async fn get_answer(&self, answer_id: i32) -> Result<Option<Answer>, Error> {
+        match sqlx::query("SELECT * from answers WHERE id = $1")
+            .bind(answer_id)
+            .map(|row: PgRow| Answer {
+                id: AnswerId(row.get("id")),
                 content: row.get("content"),
-                tags: row.get("tags"),
+                question_id: QuestionId(row.get("corresponding_question")),
+                parent_answer_id: row.get::<Option<i32>, _>("parent_answer_id").map(AnswerId),
             })
-            .fetch_all(&self.connection) // fetch_all 메서드는 SQL 문을 실행하고 추가된 질문 모두를 반환한다.
+            .fetch_optional(&self.connection)
             .await
         {
-            Ok(questions) => Ok(questions),
+            Ok(answer) => Ok(answer),
             Err(error) => {
                 tracing::event!(tracing::Level::ERROR, "{:?}", error);
                 Err(Error::DatabaseQueryError(error))
@@ -58,29 +1161,24 @@ impl Store {
         }
     }
 
-    pub async fn add_question(
-        &self,
-        new_question: NewQuestion,
-        account_id: AccountId,
-    ) -> Result<Question, Error> {
+    pub async fn get_answers_for_question(&self, question_id: i32) -> Result<Vec<Answer>, Error> {
+        // 클라이언트가 답글 트리를 만들 수 있도록 부모 답변의 id도 함께 반환한다.
         match sqlx::query(
-            "INSERT INTO questions (title, content, tags)
-            VALUES ($1, $2, $3)
-            RETURNING id, title, content, tags",
+            "SELECT * from answers
+            WHERE corresponding_question = $1
+            ORDER BY id",
         )
-        .bind(new_question.title)
-        .bind(new_question.content)
-        .bind(new_question.tags)
-        .map(|row: PgRow| Question {
-            id: QuestionId(row.get("id")),
-            title: row.get("title"),
+        .bind(question_id)
+        .map(|row: PgRow| Answer {
+            id: AnswerId(row.get("id")),
             content: row.get("content"),
-            tags: row.get("tags"),
+            question_id: QuestionId(row.get("corresponding_question")),
+            parent_answer_id: row.get::<Option<i32>, _>("parent_answer_id").map(AnswerId),
         })
-        .fetch_one(&self.connection)
+        .fetch_all(&self.connection)
         .await
         {
-            Ok(question) => Ok(question),
+            Ok(answers) => Ok(answers),
             Err(error) => {
                 tracing::event!(tracing::Level::ERROR, "{:?}", error);
                 Err(Error::DatabaseQueryError(error))
@@ -88,34 +1186,44 @@ impl Store {
         }
     }
 
-    pub async fn update_question(
+    pub async fn get_all_answers(
         &self,
-        question: Question,
-        id: i32,
-        account_id: AccountId, // 경로 핸들러에서 전달된 AccountID 매개변수를 함수에 추가한다.
-    ) -> Result<Question, Error> {
-        match sqlx::query(
-            // 질문을 수정하려는 계쩡이 해당 질문을 소유하는지 확인하는 WHERE 절을 추가한다.
-            "UPDATE questions
-            SET title = $1, content = $2, tags = $3
-            WHERE id = $4 and account_id = $5 
-            RETURNING id, title, content, tags",
-        )
-        .bind(question.title)
-        .bind(question.content)
-        .bind(question.tags)
-        .bind(id)
-        .bind(account_id.0) // AccountId에 .0으로 단일 필드 값을 바인딩한다.
-        .map(|row: PgRow| Question {
-            id: QuestionId(row.get("id")),
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
-        })
-        .fetch_one(&self.connection)
-        .await
+        limit: Option<u32>,
+        offset: u32,
+        order_by: &'static str,
+    ) -> Result<Vec<AnswerWithAuthor>, Error> {
+        // order_by는 핸들러가 허용 목록에서 고른 값만 넘기므로 쿼리 문자열에 넣어도 안전하다.
+        let query = format!(
+            "SELECT an.id, an.content, an.corresponding_question, an.parent_answer_id,
+                ac.id as author_id, ac.email as author_email
+            FROM answers an
+            LEFT JOIN accounts ac ON ac.id = an.account_id
+            ORDER BY {}
+            LIMIT $1 OFFSET $2",
+            order_by
+        );
+
+        match sqlx::query(&query)
+            .bind(limit)
+            .bind(offset)
+            .map(|row: PgRow| AnswerWithAuthor {
+                answer: Answer {
+                    id: AnswerId(row.get("id")),
+                    content: row.get("content"),
+                    question_id: QuestionId(row.get("corresponding_question")),
+                    parent_answer_id: row.get::<Option<i32>, _>("parent_answer_id").map(AnswerId),
+                },
+                author: row
+                    .get::<Option<i32>, _>("author_id")
+                    .map(|author_id| Author {
+                        id: AccountId(author_id),
+                        email: row.get("author_email"),
+                    }),
+            })
+            .fetch_all(&self.connection)
+            .await
         {
-            Ok(question) => Ok(question),
+            Ok(answers) => Ok(answers),
             Err(error) => {
                 tracing::event!(tracing::Level::ERROR, "{:?}", error);
                 Err(Error::DatabaseQueryError(error))
@@ -123,18 +1231,17 @@ impl Store {
         }
     }
 
-    pub async fn delete_question(
+    pub async fn get_answer_question_id(
         &self,
-        question_id: i32,
-        account_id: AccountId,
-    ) -> Result<bool, Error> {
-        match sqlx::query("DELETE FROM questions WHERE id = $1 AND account_id = $2")
-            .bind(question_id)
-            .bind(account_id.0)
-            .execute(&self.connection)
+        answer_id: &AnswerId,
+    ) -> Result<Option<QuestionId>, Error> {
+        match sqlx::query("SELECT corresponding_question from answers where id = $1")
+            .bind(answer_id.0)
+            .map(|row: PgRow| QuestionId(row.get("corresponding_question")))
+            .fetch_optional(&self.connection)
             .await
         {
-            Ok(_) => Ok(true),
+            Ok(question_id) => Ok(question_id),
             Err(error) => {
                 tracing::event!(tracing::Level::ERROR, "{:?}", error);
                 Err(Error::DatabaseQueryError(error))
@@ -142,28 +1249,22 @@ impl Store {
         }
     }
 
-    pub async fn add_answer(
+    pub async fn count_answers_by_account_for_question(
         &self,
-        new_answer: NewAnswer,
-        account_id: AccountId,
-    ) -> Result<Answer, Error> {
+        question_id: &QuestionId,
+        account_id: &AccountId,
+    ) -> Result<i64, Error> {
         match sqlx::query(
-            "INSERT INTO answers (content, question_id, account_id)
-        VALUES ($1, $2, $3)
-        ",
+            "SELECT COUNT(*) as count from answers
+            WHERE corresponding_question = $1 AND account_id = $2",
         )
-        .bind(new_answer.content)
-        .bind(new_answer.question_id.0)
+        .bind(question_id.0)
         .bind(account_id.0)
-        .map(|row: PgRow| Answer {
-            id: AnswerId(row.get("id")),
-            content: row.get("content"),
-            question_id: QuestionId(row.get("corresponding_question")),
-        })
+        .map(|row: PgRow| row.get("count"))
         .fetch_one(&self.connection)
         .await
         {
-            Ok(answer) => Ok(answer),
+            Ok(count) => Ok(count),
             Err(error) => {
                 tracing::event!(tracing::Level::ERROR, "{:?}", error);
                 Err(Error::DatabaseQueryError(error))
@@ -197,6 +1298,159 @@ impl Store {
         }
     }
 
+    /// 저장된 해시가 current와 같을 때만 비밀번호를 바꾼다.
+    /// 그 사이에 비밀번호가 이미 바뀌었다면 아무것도 바꾸지 않고 false를 반환한다.
+    pub async fn replace_password(
+        &self,
+        account_id: AccountId,
+        current: &str,
+        password: String,
+    ) -> Result<bool, Error> {
+        match sqlx::query("UPDATE accounts SET password = $1 WHERE id = $2 AND password = $3")
+            .bind(password)
+            .bind(account_id.0)
+            .bind(current)
+            .execute(&self.connection)
+            .await
+        {
+            Ok(result) => Ok(result.rows_affected() == 1),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn update_email(&self, account_id: AccountId, email: String) -> Result<bool, Error> {
+        // 새 주소는 아직 인증하지 않았으므로 인증 여부를 되돌린다.
+        match sqlx::query("UPDATE accounts SET email = $1, email_verified = false WHERE id = $2")
+            .bind(email)
+            .bind(account_id.0)
+            .execute(&self.connection)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                // 확인한 뒤 다른 요청이 같은 이메일을 먼저 가져간 경우에도 중복 에러로 알려준다.
+                if error.as_database_error().and_then(|e| e.code()).as_deref() == Some("23505") {
+                    return Err(Error::EmailTaken);
+                }
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 계정의 이메일을 인증된 것으로 표시한다. 계정이 없으면 false를 돌려준다.
+    pub async fn verify_email(&self, account_id: &AccountId) -> Result<bool, Error> {
+        match sqlx::query("UPDATE accounts SET email_verified = true WHERE id = $1")
+            .bind(account_id.0)
+            .execute(&self.connection)
+            .await
+        {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn is_email_verified(&self, account_id: &AccountId) -> Result<bool, Error> {
+        match sqlx::query("SELECT email_verified FROM accounts WHERE id = $1")
+            .bind(account_id.0)
+            .map(|row: PgRow| row.get("email_verified"))
+            .fetch_optional(&self.connection)
+            .await
+        {
+            Ok(verified) => Ok(verified.unwrap_or(false)),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn account_exists(&self, email: String) -> Result<bool, Error> {
+        match sqlx::query("SELECT 1 from accounts where email = $1")
+            .bind(email)
+            .fetch_optional(&self.connection)
+            .await
+        {
+            Ok(account) => Ok(account.is_some()),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_account_stats(&self, account_id: AccountId) -> Result<AccountStats, Error> {
+        match sqlx::query(
+            // 두 번 왕복하지 않도록 하위 쿼리로 질문 수와 답변 수를 한 번에 센다.
+            "SELECT
+                (SELECT COUNT(*) FROM questions WHERE account_id = $1) as questions,
+                (SELECT COUNT(*) FROM answers WHERE account_id = $1) as answers",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| AccountStats {
+            questions: row.get("questions"),
+            answers: row.get("answers"),
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(stats) => Ok(stats),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_site_stats(&self) -> Result<SiteStats, Error> {
+        match sqlx::query(
+            // get_account_stats처럼 하위 쿼리로 세 테이블을 한 번에 센다.
+            "SELECT
+                (SELECT COUNT(*) FROM questions) as questions,
+                (SELECT COUNT(*) FROM answers) as answers,
+                (SELECT COUNT(*) FROM accounts) as accounts",
+        )
+        .map(|row: PgRow| SiteStats {
+            questions: row.get("questions"),
+            answers: row.get("answers"),
+            accounts: row.get("accounts"),
+        })
+        .fetch_one(self.reader())
+        .await
+        {
+            Ok(stats) => Ok(stats),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    pub async fn get_account_by_id(&self, account_id: &AccountId) -> Result<Account, Error> {
+        match sqlx::query("SELECT * from accounts where id = $1")
+            .bind(account_id.0)
+            .map(|row: PgRow| Account {
+                id: Some(AccountId(row.get("id"))),
+                email: row.get("email"),
+                password: row.get("password"),
+            })
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(account) => Ok(account),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
     pub async fn get_account(self, email: String) -> Result<Account, Error> {
         match sqlx::query("SELECT * from accounts where email = $1")
             .bind(email)
@@ -216,6 +1470,95 @@ impl Store {
         }
     }
 
+    pub async fn add_api_key(
+        &self,
+        account_id: AccountId,
+        key_hash: String,
+    ) -> Result<ApiKey, Error> {
+        match sqlx::query(
+            "INSERT INTO api_keys (account_id, key_hash)
+            VALUES ($1, $2)
+            RETURNING id, created_on, revoked_on",
+        )
+        .bind(account_id.0)
+        .bind(key_hash)
+        .map(|row: PgRow| ApiKey {
+            id: row.get("id"),
+            created_on: row.get("created_on"),
+            revoked_on: row.get("revoked_on"),
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(api_key) => Ok(api_key),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 폐기되지 않은 API 키의 계정 id와 해시를 돌려준다.
+    pub async fn get_api_key_hash(
+        &self,
+        api_key_id: i32,
+    ) -> Result<Option<(AccountId, String)>, Error> {
+        match sqlx::query(
+            "SELECT account_id, key_hash FROM api_keys
+            WHERE id = $1 AND revoked_on IS NULL",
+        )
+        .bind(api_key_id)
+        .map(|row: PgRow| (AccountId(row.get("account_id")), row.get("key_hash")))
+        .fetch_optional(&self.connection)
+        .await
+        {
+            Ok(api_key) => Ok(api_key),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 계정의 API 키를 폐기한다. 키가 없거나, 다른 계정의 키이거나, 이미 폐기되었으면 false를 돌려준다.
+    pub async fn revoke_api_key(
+        &self,
+        api_key_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "UPDATE api_keys SET revoked_on = NOW()
+            WHERE id = $1 AND account_id = $2 AND revoked_on IS NULL",
+        )
+        .bind(api_key_id)
+        .bind(account_id.0)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(result) => Ok(result.rows_affected() > 0),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
+    /// 소유자와 관계없이 질문이 있는지 확인한다.
+    /// 권한 없음(401)과 없는 질문(404)을 구분할 때 쓰므로 복제본이 아닌 connection에서 읽는다.
+    pub async fn question_exists(&self, question_id: i32) -> Result<bool, Error> {
+        match sqlx::query("SELECT 1 from questions where id = $1")
+            .bind(question_id)
+            .fetch_optional(&self.connection)
+            .await
+        {
+            Ok(question) => Ok(question.is_some()),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                Err(Error::DatabaseQueryError(error))
+            }
+        }
+    }
+
     pub async fn is_question_owner(
         &self,
         question_id: i32,
@@ -235,3 +1578,24 @@ impl Store {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_tags_keeps_null_column_as_none() {
+        assert_eq!(normalize_tags(None), None);
+    }
+
+    #[test]
+    fn normalize_tags_keeps_empty_array() {
+        assert_eq!(normalize_tags(Some(vec![])), Some(vec![]));
+    }
+
+    #[test]
+    fn normalize_tags_drops_null_items_and_duplicates() {
+        let tags = Some(vec![Some("a".to_string()), None, Some("a".to_string())]);
+        assert_eq!(normalize_tags(tags), Some(vec!["a".to_string()]));
+    }
+}
@@ -1,14 +1,21 @@
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// PASETO 토큰의 클레임에서 만든 세션
+/// exp(만료 시각)와 nbf(유효 시작 시각)가 없는 예전 토큰도 역직렬화할 수 있도록 Option으로 둔다.
+/// 이미 발급된 토큰의 클레임 이름(account_id)이 바뀌지 않도록 camelCase로 바꾸지 않는다.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Session {
-    pub exp: DateTime<Utc>,
+    #[serde(default)]
+    pub exp: Option<DateTime<Utc>>,
     pub account_id: AccountId,
-    pub nbf: DateTime<Utc>,
+    #[serde(default)]
+    pub nbf: Option<DateTime<Utc>>,
 }
 
+/// 등록과 로그인 요청 본문으로도 쓰이므로 알 수 없는 필드(is_admin 등)가 있으면 역직렬화를 거부한다.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Account {
     pub id: Option<AccountId>,
     pub email: String,
@@ -17,3 +24,83 @@ pub struct Account {
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AccountId(pub i32);
+
+/// POST /registration 요청 본문
+/// id는 데이터베이스가 정하므로 받지 않고, id를 포함해 알 수 없는 필드가 있으면 거부한다.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub password: String,
+}
+
+/// 로그인에 성공했을 때 반환하는 본문
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginResponse {
+    pub token: String,
+    pub token_type: String,
+    pub expires_in: i64, // 토큰이 만료되기까지 남은 시간(초)
+}
+
+/// 비밀번호 재설정 토큰을 요청할 때 받는 본문
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PasswordResetRequest {
+    pub email: String,
+}
+
+/// 재설정 토큰과 새 비밀번호로 비밀번호를 바꿀 때 받는 본문
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PasswordResetConfirm {
+    pub token: String,
+    pub password: String,
+}
+
+/// POST /verify-email 요청 본문
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailVerification {
+    pub token: String,
+}
+
+/// 이메일을 바꿀 때 받는 본문으로, 본인 확인을 위해 현재 비밀번호를 함께 받는다.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EmailChange {
+    pub email: String,
+    pub password: String,
+}
+
+/// 되돌릴 수 없는 요청(DELETE /me/questions 등)에서 본인 확인을 위해 받는 본문
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PasswordConfirmation {
+    pub password: String,
+}
+
+/// 계정에 발급된 API 키
+/// 키 원문은 저장하지 않으므로 id와 발급, 폐기 시각만 담는다.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: i32,
+    #[serde(with = "crate::types::timestamp")]
+    pub created_on: DateTime<Utc>,
+    #[serde(with = "crate::types::timestamp::option")]
+    pub revoked_on: Option<DateTime<Utc>>,
+}
+
+/// POST /me/api-keys 응답 본문으로, 키 원문은 이때 한 번만 돌려준다.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NewApiKey {
+    pub id: i32,
+    pub key: String,
+    #[serde(with = "crate::types::timestamp")]
+    pub created_on: DateTime<Utc>,
+}
+
+/// 계정이 남긴 질문과 답변의 수
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStats {
+    pub questions: i64,
+    pub answers: i64,
+}
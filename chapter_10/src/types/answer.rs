@@ -1,19 +1,64 @@
+use handle_errors::Error;
 use serde::{Deserialize, Serialize};
 
-use crate::types::question::QuestionId;
+use crate::types::question::{Author, QuestionId, max_length_from_env};
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AnswerId(pub i32);
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Answer {
     pub id: AnswerId,
     pub content: String,
     pub question_id: QuestionId,
+    pub parent_answer_id: Option<AnswerId>, // 다른 답변에 단 답글이라면 부모 답변의 id
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct NewAnswer {
     pub content: String,
     pub question_id: QuestionId,
+    pub parent_answer_id: Option<AnswerId>,
+}
+
+/// PATCH /answers/:id 요청 본문으로, 값이 있는 필드만 바꾼다.
+/// 지금은 content만 받지만 필드를 추가하면 store::update_answer의 SET 절에도 함께 추가한다.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AnswerUpdate {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+impl AnswerUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.content.is_none()
+    }
+}
+
+/// 답변 내용이 최대 길이(글자 수)를 넘지 않는지 확인한다.
+/// ANSWER_CONTENT_MAX_LENGTH(기본 5000)로 바꿀 수 있다.
+pub fn validate_answer_length(content: &str) -> Result<(), Error> {
+    let max_content = max_length_from_env("ANSWER_CONTENT_MAX_LENGTH", 5000)?;
+
+    if content.chars().count() > max_content {
+        return Err(Error::InvalidInput(format!(
+            "content must be at most {} characters",
+            max_content
+        )));
+    }
+
+    Ok(())
+}
+
+/// GET /admin/answers에서 반환하는 답변과 작성자
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerWithAuthor {
+    #[serde(flatten)]
+    pub answer: Answer,
+    /// 작성자 계정이 없는 예전 답변은 null이다.
+    pub author: Option<Author>,
 }
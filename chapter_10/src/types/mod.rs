@@ -1,4 +1,14 @@
+//! API에서 주고받는 타입
+//!
+//! 요청과 응답의 JSON 필드 이름은 camelCase(questionId, createdOn 등)로 통일한다.
+//! 러스트 필드 이름은 snake_case로 두고 #[serde(rename_all = "camelCase")]로 바꾼다.
+//! 토큰의 클레임(Session)과 외부 API(APILayer)의 응답 형식은 그대로 둔다.
+//! 응답의 시각은 timestamp 모듈로 RFC3339 형식의 UTC 문자열로 직렬화한다.
+
 pub mod account;
 pub mod answer;
+pub mod moderation;
 pub mod pagination;
 pub mod question;
+pub mod stats;
+pub mod timestamp;
@@ -0,0 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+/// POST /moderation/check로 미리 검사할 내용
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModerationRequest {
+    pub content: String,
+}
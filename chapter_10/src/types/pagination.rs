@@ -1,6 +1,9 @@
 use handle_errors::Error;
+use serde::Serialize;
 use std::collections::HashMap;
 
+use crate::filters::PRETTY_PARAM;
+
 /// Pagination 구조체는 쿼리 매개변수에서 추출된다
 #[derive(Default, Debug)]
 pub struct Pagination {
@@ -12,24 +15,127 @@ pub struct Pagination {
     /// 두 번째 매개변수는 offset으로 값이 0이면 PostgreSQL은 이 값을 무시한다.
     /// 제한 필드와 동일하다. 덕분에 if 문을 쓰지 않아도 된다.
     pub offset: u32,
+    /// 커서 모드에서 마지막으로 받은 아이템의 id
+    /// 값이 있으면 offset 대신 이 id보다 큰 아이템만 반환한다.
+    pub after: Option<i32>,
+}
+
+impl Pagination {
+    /// limit이 없으면 DEFAULT_PAGE_SIZE(기본 20)를 쓰고, MAX_PAGE_SIZE(기본 100)를 넘으면 최대값으로 줄인다.
+    /// 값을 줄였다면 적용한 최대값을 반환한다.
+    pub fn apply_page_size(&mut self) -> Result<Option<u32>, Error> {
+        let default_page_size = page_size_from_env("DEFAULT_PAGE_SIZE", 20)?;
+        let max_page_size = page_size_from_env("MAX_PAGE_SIZE", 100)?;
+
+        match self.limit {
+            None => {
+                self.limit = Some(default_page_size.min(max_page_size));
+                Ok(None)
+            }
+            Some(limit) if limit > max_page_size => {
+                self.limit = Some(max_page_size); // 에러 대신 최대값으로 줄인다.
+                Ok(Some(max_page_size))
+            }
+            Some(_) => Ok(None),
+        }
+    }
+
+    /// 다음 페이지의 offset으로, limit이 없거나 u32 범위를 넘으면 None이다.
+    pub fn next_offset(&self) -> Option<u32> {
+        self.offset.checked_add(self.limit?)
+    }
+
+    /// 이전 페이지의 offset으로, 첫 페이지이거나 limit이 없으면 None이다.
+    /// offset이 limit보다 작으면 0부터 시작하는 페이지를 가리킨다.
+    pub fn prev_offset(&self) -> Option<u32> {
+        if self.offset == 0 {
+            return None;
+        }
+        Some(self.offset.saturating_sub(self.limit?))
+    }
+
+    /// 이전/다음 페이지 주소를 담은 Link 헤더 값을 만든다.
+    /// has_next가 false이면(마지막 페이지) next는 넣지 않는다.
+    pub fn link_header(&self, path: &str, has_next: bool) -> Option<String> {
+        let limit = self.limit?;
+        let mut links = Vec::new();
+
+        if let Some(prev) = self.prev_offset() {
+            links.push(format!(
+                "<{}?limit={}&offset={}>; rel=\"prev\"",
+                path, limit, prev
+            ));
+        }
+        if let Some(next) = self.next_offset().filter(|_| has_next) {
+            links.push(format!(
+                "<{}?limit={}&offset={}>; rel=\"next\"",
+                path, limit, next
+            ));
+        }
+
+        if links.is_empty() {
+            None
+        } else {
+            Some(links.join(", "))
+        }
+    }
+}
+
+fn page_size_from_env(name: &str, default: u32) -> Result<u32, Error> {
+    std::env::var(name)
+        .ok()
+        .map(|val| val.parse::<u32>())
+        .unwrap_or(Ok(default))
+        .map_err(Error::ParseError)
+}
+
+/// 커서 모드로 조회한 한 페이지와 다음 페이지를 요청할 때 사용할 커서
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    /// 더 가져올 아이템이 없으면 None이다.
+    pub next_cursor: Option<i32>,
 }
 
 /// 매개변수를 /questions 경로에서 추출하기
 /// # 예제 쿼리
 /// 이 경로에 대한 GET 요청에는 반환 받기 원하는 질문만 반환 받도록
 /// 페이지 정보가 추가될 수 있다
-/// /questions?start=1&end=10
+/// /questions?limit=1&offset=10
 /// # 사용 예
-/// ```rust
+/// 바이너리 크레이트라 문서 테스트로 실행되지 않으므로 ignore로 표시하고, 같은 내용을 아래 tests 모듈에서 확인한다.
+/// ```rust,ignore
 /// let mut query = HashMap::new();
 /// query.insert("limit".to_string(), "1".to_string());
 /// query.insert("offset".to_string(), "10".to_string());
 /// let p = types::pagination::extract_pagination(query).unwrap();
-/// assert_eq!(p.limit, 1);
+/// assert_eq!(p.limit, Some(1));
 /// assert_eq!(p.offset, 10);
+/// assert_eq!(p.after, None);
 /// ```
 
 pub fn extract_pagination(params: HashMap<String, String>) -> Result<Pagination, Error> {
+    // after가 있으면 커서 모드로, limit은 생략할 수 있다.
+    // /questions?after=10&limit=5
+    if params.contains_key("after") {
+        return Ok(Pagination {
+            limit: params
+                .get("limit")
+                .map(|limit| limit.parse::<u32>())
+                .transpose()
+                .map_err(Error::ParseError)?,
+            offset: 0,
+            after: Some(
+                params
+                    .get("after")
+                    .unwrap()
+                    .parse::<i32>()
+                    .map_err(Error::ParseError)?,
+            ),
+        });
+    }
+
     // 나중에 더 개선할 수 있다
     if params.contains_key("limit") && params.contains_key("offset") {
         return Ok(Pagination {
@@ -49,8 +155,97 @@ pub fn extract_pagination(params: HashMap<String, String>) -> Result<Pagination,
                 .unwrap()
                 .parse::<u32>() // &str을 u32로 변환한다.
                 .map_err(Error::ParseError)?,
+            after: None,
         });
     }
 
-    Err(Error::MissingParameters)
+    // 어떤 매개변수가 빠졌는지 알려준다. 둘 다 없으면 limit부터 요구한다.
+    if params.contains_key("limit") {
+        Err(Error::MissingParameter("offset".to_string()))
+    } else {
+        Err(Error::MissingParameter("limit".to_string()))
+    }
+}
+
+/// Range 헤더에서 페이지 정보를 추출한다.
+/// 쿼리 매개변수 대신 Range: items=0-9 형식으로 범위를 요청할 수 있다.
+/// 끝 값도 포함하므로 items=0-9는 limit=10&offset=0과 같다.
+pub fn extract_range(range: &str) -> Result<Pagination, Error> {
+    let (start, end) = range
+        .trim()
+        .strip_prefix("items=")
+        .and_then(|range| range.split_once('-'))
+        .ok_or(Error::RangeNotSatisfiable)?;
+
+    let start = start
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| Error::RangeNotSatisfiable)?;
+    let end = end
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| Error::RangeNotSatisfiable)?;
+
+    if end < start {
+        return Err(Error::RangeNotSatisfiable);
+    }
+
+    Ok(Pagination {
+        limit: Some((end - start).saturating_add(1)),
+        offset: start,
+        after: None,
+    })
+}
+
+/// STRICT_QUERY_PARAMS=true일 때 허용 목록에 없는 쿼리 매개변수가 있으면 에러를 반환한다.
+/// 기본값은 느슨한 모드로, 알 수 없는 매개변수를 무시한다.
+/// ?limt=10 같은 오타를 조용히 넘기지 않도록 할 때 사용한다.
+pub fn reject_unknown_params(
+    params: &HashMap<String, String>,
+    allowed: &[&str],
+) -> Result<(), Error> {
+    if std::env::var("STRICT_QUERY_PARAMS").as_deref() != Ok("true") {
+        return Ok(());
+    }
+
+    let mut unknown: Vec<String> = params
+        .keys()
+        .filter(|key| key.as_str() != PRETTY_PARAM && !allowed.contains(&key.as_str()))
+        .cloned()
+        .collect();
+
+    if unknown.is_empty() {
+        return Ok(());
+    }
+
+    unknown.sort(); // 응답 메시지가 항상 같은 순서가 되도록 정렬한다.
+    Err(Error::UnknownParameters(unknown))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_pagination_reads_limit_and_offset() {
+        let mut query = HashMap::new();
+        query.insert("limit".to_string(), "1".to_string());
+        query.insert("offset".to_string(), "10".to_string());
+
+        let p = extract_pagination(query).unwrap();
+        assert_eq!(p.limit, Some(1));
+        assert_eq!(p.offset, 10);
+        assert_eq!(p.after, None);
+    }
+
+    #[test]
+    fn extract_pagination_requires_offset_with_limit() {
+        let mut query = HashMap::new();
+        query.insert("limit".to_string(), "1".to_string());
+
+        assert!(matches!(
+            extract_pagination(query),
+            Err(Error::MissingParameter(name)) if name == "offset"
+        ));
+    }
 }
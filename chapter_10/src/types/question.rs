@@ -1,19 +1,246 @@
+use chrono::prelude::*;
+use handle_errors::Error;
 use serde::{Deserialize, Serialize};
 
+use crate::types::account::AccountId;
+use crate::types::answer::Answer;
+
 #[derive(Serialize, Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct Question {
     pub id: QuestionId,
     pub title: String,
     pub content: String,
     pub tags: Option<Vec<String>>,
+    /// 질문을 작성한 계정으로, 이메일은 노출하지 않고 id만 반환한다.
+    /// 수정 요청 본문에서는 받지 않아도 되며 보내더라도 무시한다.
+    #[serde(default)]
+    pub account_id: Option<AccountId>,
+    /// 답변 수로, 답변이 추가되거나 삭제될 때 데이터베이스 트리거가 갱신한다. 수정 요청 본문에서는 무시한다.
+    #[serde(default)]
+    pub answer_count: i32,
+    /// 질문의 상태로, PATCH /questions/:id/status로만 바꿀 수 있다. 수정 요청 본문에서는 무시한다.
+    #[serde(default)]
+    pub status: QuestionStatus,
 }
 
 #[derive(Serialize, Debug, Clone, Eq, Hash, Deserialize, PartialEq)]
 pub struct QuestionId(pub i32);
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct NewQuestion {
     pub title: String,
     pub content: String,
     pub tags: Option<Vec<String>>,
 }
+
+/// POST /questions 요청 본문으로, 질문 하나(객체)나 여러 개(배열)를 모두 받는다.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum NewQuestions {
+    One(NewQuestion),
+    Many(Vec<NewQuestion>),
+}
+
+/// POST /questions/:id/tags 요청 본문으로, 질문에 태그 하나를 더한다.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TagChange {
+    pub tag: String,
+}
+
+/// 질문의 상태로, 데이터베이스에는 소문자 문자열(open, resolved, closed)로 저장한다.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionStatus {
+    #[default]
+    Open,
+    Resolved,
+    Closed,
+}
+
+impl QuestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionStatus::Open => "open",
+            QuestionStatus::Resolved => "resolved",
+            QuestionStatus::Closed => "closed",
+        }
+    }
+
+    /// 현재 상태에서 next로 바꿀 수 있는지 확인한다.
+    /// 닫힌 질문은 관리자만 다시 열거나 resolved로 되돌릴 수 있다.
+    pub fn can_transition_to(&self, next: QuestionStatus, is_admin: bool) -> bool {
+        match (self, next) {
+            (QuestionStatus::Closed, QuestionStatus::Closed) => true,
+            (QuestionStatus::Closed, _) => is_admin,
+            _ => true,
+        }
+    }
+}
+
+impl std::str::FromStr for QuestionStatus {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "open" => Ok(QuestionStatus::Open),
+            "resolved" => Ok(QuestionStatus::Resolved),
+            "closed" => Ok(QuestionStatus::Closed),
+            _ => Err(Error::InvalidInput(format!(
+                "status must be one of open, resolved, closed: {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// PATCH /questions/:id/status 요청 본문
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct QuestionStatusChange {
+    pub status: QuestionStatus,
+}
+
+/// ?fields=로 고를 수 있는 질문 필드의 응답 이름과 데이터베이스 열 이름
+/// SELECT 절은 이 목록의 열 이름으로만 만들므로 사용자 입력이 쿼리에 그대로 들어가지 않는다.
+pub const QUESTION_FIELDS: &[(&str, &str)] = &[
+    ("id", "id"),
+    ("title", "title"),
+    ("content", "content"),
+    ("tags", "tags"),
+    ("accountId", "account_id"),
+    ("answerCount", "answer_count"),
+    ("status", "status"),
+];
+
+/// 쉼표로 구분한 필드 이름(예: id,title)을 (응답 이름, 열 이름) 목록으로 바꾼다.
+/// 목록에 없는 이름이 하나라도 있으면 에러를 반환한다.
+pub fn parse_fields(fields: &str) -> Result<Vec<(&'static str, &'static str)>, Error> {
+    let mut selected = Vec::new();
+    let mut unknown = Vec::new();
+
+    for name in fields
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        match QUESTION_FIELDS.iter().find(|(field, _)| *field == name) {
+            Some(field) if !selected.contains(field) => selected.push(*field),
+            Some(_) => {}
+            None => unknown.push(name.to_string()),
+        }
+    }
+
+    if !unknown.is_empty() {
+        return Err(Error::UnknownFields(unknown));
+    }
+    if selected.is_empty() {
+        return Err(Error::InvalidInput("fields must not be empty".to_string()));
+    }
+
+    Ok(selected)
+}
+
+/// 제목과 내용이 최대 길이(글자 수)를 넘지 않는지 확인한다.
+/// QUESTION_TITLE_MAX_LENGTH(기본 150)와 QUESTION_CONTENT_MAX_LENGTH(기본 10000)로 바꿀 수 있다.
+/// 외부 API를 호출하기 전에 확인해 너무 긴 요청은 바로 거른다.
+pub fn validate_lengths(title: &str, content: &str) -> Result<(), Error> {
+    let max_title = max_length_from_env("QUESTION_TITLE_MAX_LENGTH", 150)?;
+    let max_content = max_length_from_env("QUESTION_CONTENT_MAX_LENGTH", 10000)?;
+
+    if title.chars().count() > max_title {
+        return Err(Error::InvalidInput(format!(
+            "title must be at most {} characters",
+            max_title
+        )));
+    }
+
+    if content.chars().count() > max_content {
+        return Err(Error::InvalidInput(format!(
+            "content must be at most {} characters",
+            max_content
+        )));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn max_length_from_env(name: &str, default: usize) -> Result<usize, Error> {
+    std::env::var(name)
+        .ok()
+        .map(|val| val.parse::<usize>())
+        .unwrap_or(Ok(default))
+        .map_err(Error::ParseError)
+}
+
+/// 질문 작성자의 공개 정보로, 비밀번호 같은 계정 정보는 담지 않는다.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Author {
+    pub id: AccountId,
+    /// HIDE_AUTHOR_EMAIL=true이면 응답에서 뺀다.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// GET /questions/:id?include=author로 조회한 질문과 작성자
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionWithAuthor {
+    #[serde(flatten)]
+    pub question: Question,
+    /// 작성자 계정이 없는 예전 질문은 null이다.
+    pub author: Option<Author>,
+}
+
+/// GET /questions/:id/export로 내보내는 질문 한 건의 전체 내용
+/// 다른 요청 없이 보관할 수 있도록 작성자와 모든 답변을 함께 담는다.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionExport {
+    pub question: QuestionWithAuthor,
+    pub answers: Vec<Answer>,
+    #[serde(with = "crate::types::timestamp")]
+    pub exported_at: DateTime<Utc>,
+}
+
+/// GET /questions/search로 찾은 질문
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub question: Question,
+    /// ?highlight=true일 때만 채워지며, 내용 중 검색어와 일치한 부분을 <b></b>로 감싼 발췌문이다.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<String>,
+}
+
+/// 질문이 수정되기 전의 내용을 담는 이력 항목
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuestionHistory {
+    pub id: i32,
+    pub question_id: QuestionId,
+    pub title: String,
+    pub content: String,
+    pub tags: Option<Vec<String>>,
+    pub account_id: AccountId, // 수정을 한 계정
+    #[serde(with = "crate::types::timestamp")]
+    pub created_on: DateTime<Utc>,
+}
+
+/// 태그 하나와 그 태그가 붙은 질문 수
+#[derive(Serialize, Debug, Clone)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// POST /admin/maintenance/recount-tags의 결과
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TagRecount {
+    /// NULL 항목이나 중복된 태그를 정리한 질문 수
+    pub fixed_questions: u64,
+    pub tags: Vec<TagCount>,
+}
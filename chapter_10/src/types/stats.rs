@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// GET /stats에서 반환하는 사이트 전체의 질문, 답변, 계정 수
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteStats {
+    pub questions: i64,
+    pub answers: i64,
+    pub accounts: i64,
+}
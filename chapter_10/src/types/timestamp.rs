@@ -0,0 +1,55 @@
+//! 응답의 시각을 RFC3339 형식의 UTC 문자열(2024-01-02T03:04:05Z)로 주고받는 serde 도우미
+//!
+//! 클라이언트가 항상 같은 형식으로 파싱할 수 있도록 초 단위까지만 쓰고 시간대는 Z로 표시한다.
+//! 응답에 들어가는 DateTime<Utc> 필드(created_on, updated_on 등)에
+//! #[serde(with = "crate::types::timestamp")]를 붙여 사용한다.
+//! Option 필드에는 #[serde(with = "crate::types::timestamp::option")]를 사용한다.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// 다른 시간대로 표시한 값도 받아서 UTC로 바꾼다.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&value)
+        .map(|value| value.with_timezone(&Utc))
+        .map_err(serde::de::Error::custom)
+}
+
+/// 값이 없으면 null로 주고받는다.
+pub mod option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(value) => super::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) => DateTime::parse_from_rfc3339(&value)
+                .map(|value| Some(value.with_timezone(&Utc)))
+                .map_err(serde::de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}